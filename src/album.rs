@@ -6,8 +6,15 @@ use std::{
 };
 
 use futures::{
+    future::{
+        self,
+        Loop,
+    },
     Future,
 };
+use reqwest::{
+    Url,
+};
 
 use crate::{
     album_types::{
@@ -18,14 +25,31 @@ use crate::{
         ClientRing,
     },
     common_types::{
+        AlbumsEnvelope,
         Paging,
     },
+    ids::{
+        AlbumId,
+        join_ids,
+    },
+    market::{
+        Market,
+        with_market,
+    },
+    search::{
+        search,
+        SearchQuery,
+        SearchType,
+    },
     track_types::{
         TrackSimple,
     },
     utils::{
+        ErrorKind,
+        get_next_paging,
+        get_item_lookups_with_retry,
         get_with_retry,
-        search,
+        ItemLookup,
         SimpleError,
     },
 };
@@ -34,61 +58,108 @@ type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
 
 pub fn get_album(
     client_ring: Arc<RwLock<ClientRing>>,
-    album_id: String,
+    (album_id, market): (AlbumId, Option<Market>),
 ) -> CustomFuture<AlbumFull> {
     Box::new(
         get_with_retry(
-            format!("https://api.spotify.com/v1/albums/{}/", album_id),
+            with_market(format!("https://api.spotify.com/v1/albums/{}/", album_id), &market),
             client_ring,
         )
     )
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct AlbumTracksOptions {
+    // page size, at most 50
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+    pub market: Option<Market>,
+}
+
+impl AlbumTracksOptions {
+    fn url(
+        &self,
+        album_id: &AlbumId,
+    ) -> String {
+        let mut params = Vec::new();
+        self.limit.map(|limit| params.push(("limit", limit.to_string())));
+        self.offset.map(|offset| params.push(("offset", offset.to_string())));
+        self.market.as_ref().map(|market| params.push(("market", market.to_string())));
+
+        Url::parse_with_params(
+            &format!("https://api.spotify.com/v1/albums/{}/tracks/", album_id)[..],
+            params,
+        ).expect("Error in album::AlbumTracksOptions::url format").into_string()
+    }
+}
+
 pub fn get_album_tracks(
     client_ring: Arc<RwLock<ClientRing>>,
-    album_id: String,
-) -> CustomFuture<TrackSimple> {
+    (album_id, options): (AlbumId, AlbumTracksOptions),
+) -> CustomFuture<Paging<TrackSimple>> {
     Box::new(
         get_with_retry(
-            format!("https://api.spotify.com/v1/albums/{}/tracks/", album_id),
+            options.url(&album_id),
             client_ring,
         )
     )
 }
 
-pub fn get_albums(
+// The complete tracklist, following next pages 50 tracks at a time. Any
+// failing page fails the whole future, so it can be retried with
+// loop_until_ok.
+pub fn get_album_tracklist(
     client_ring: Arc<RwLock<ClientRing>>,
-    album_ids: Vec<String>,
-) -> CustomFuture<Vec<AlbumFull>> {
+    (album_id, market): (AlbumId, Option<Market>),
+) -> CustomFuture<Vec<TrackSimple>> {
+    let options = AlbumTracksOptions {
+        limit: Some(50),
+        offset: None,
+        market,
+    };
+
     Box::new(
-        get_with_retry::<serde_json::Value>(
-            format!(
-                "https://api.spotify.com/v1/albums/?ids={}",
-                album_ids.join(","),
-            ),
-            client_ring,
-        ).map(|value| {
-            value.get("albums").expect("Error in album::get_albums format")
-                .as_array().expect("Error in album::get_albums format")
-                .into_iter().map(|value| {
-                    serde_json::from_value::<AlbumFull>(value.to_owned())
-                        .expect("Error in album::get_albums format")
-                }).collect()
+        get_album_tracks(client_ring.clone(), (album_id, options)).and_then(|paging| {
+            future::loop_fn((paging.items, paging.next), move |(mut tracks, next)| {
+                let next_paging_url = match next {
+                    Some(next_paging_url) => next_paging_url,
+                    None => return future::Either::A(future::ok(Loop::Break(tracks))),
+                };
+
+                future::Either::B(get_next_paging(client_ring.clone(), next_paging_url).map(|paging| {
+                    tracks.extend(paging.items);
+                    Loop::Continue((tracks, paging.next))
+                }))
+            })
         })
     )
 }
 
+pub fn get_albums(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (album_ids, market): (Vec<AlbumId>, Option<Market>),
+) -> CustomFuture<Vec<ItemLookup<AlbumFull>>> {
+    get_item_lookups_with_retry::<_, AlbumsEnvelope<_>>(
+        with_market(format!(
+            "https://api.spotify.com/v1/albums/?ids={}",
+            join_ids(&album_ids),
+        ), &market),
+        album_ids.len(),
+        client_ring,
+    )
+}
+
 pub fn search_albums(
     client_ring: Arc<RwLock<ClientRing>>,
     query: String,
 ) -> CustomFuture<Paging<AlbumSimple>> {
     Box::new(
-        search::<serde_json::Value>(query, "album", client_ring)
-            .map(|value| {
-                serde_json::from_value(
-                    value.get("albums").expect("Error in album::search_albums format")
-                        .to_owned()
-                ).expect("Error in album::search_albums format")
+        search(client_ring, SearchQuery::keywords(query, SearchType::Album))
+            .and_then(|results| {
+                results.albums.ok_or(Box::new(SimpleError {
+                    message: "No albums in album::search_albums results".to_string(),
+                    kind: ErrorKind::Permanent,
+                }))
             })
     )
 }
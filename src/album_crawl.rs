@@ -35,6 +35,7 @@ use crate::{
         AlbumCsv,
     },
     artist::{
+        ArtistAlbumsOptions,
         get_artist_albums,
     },
     artist_types::{
@@ -48,7 +49,11 @@ use crate::{
         read_csv_into_sender,
         write_csv_through_receiver,
     },
+    telemetry::{
+        Telemetry,
+    },
     utils::{
+        ErrorKind,
         get_next_paging,
         loop_until_ok,
         SimpleError,
@@ -57,9 +62,11 @@ use crate::{
 
 fn crawl_artists_albums_thread(
     artists_crawled: Receiver<ArtistCsv>,
+    options: ArtistAlbumsOptions,
     client_ring: Arc<RwLock<ClientRing>>,
     sender: Sender<AlbumCsv>,
     progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let mut rt = Runtime::new().expect("No tokio runtime");
@@ -68,7 +75,7 @@ fn crawl_artists_albums_thread(
             let (mut items, mut next) = rt.block_on(loop_until_ok(
                 &get_artist_albums,
                 client_ring.clone(),
-                artist_csv.id.clone(),
+                (artist_csv.id.clone(), options.clone()),
             ).map(|paging| {
                 (Some(paging.items), paging.next)
             })).unwrap_or_else(|err| {
@@ -90,6 +97,7 @@ fn crawl_artists_albums_thread(
                         )
                     ).map_err(|err| SimpleError {
                         message: err.to_string(),
+                        kind: ErrorKind::Permanent,
                     }.into())
                 }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
                     error!(
@@ -121,12 +129,14 @@ fn crawl_artists_albums_thread(
             }
 
             progress.inc(1);
+            telemetry.record_processed("album_crawl", 1);
         }
     })
 }
 
 pub fn album_crawl(
     artists_crawled: Receiver<ArtistCsv>,
+    options: ArtistAlbumsOptions,
     client_ring: Arc<RwLock<ClientRing>>,
     sender: Sender<AlbumCsv>,
 ) -> thread::Result<()> {
@@ -140,15 +150,18 @@ pub fn album_crawl(
             .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
     );
 
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let num_threads = num_cpus::get();
     info!("Using {} threads", num_threads);
     
     let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
         crawl_artists_albums_thread(
             artists_crawled.clone(),
+            options.clone(),
             client_ring.clone(),
             sender.clone(),
             progress.clone(),
+            telemetry.clone(),
         )
     }).collect();
 
@@ -162,18 +175,23 @@ pub fn album_crawl(
 
 #[allow(dead_code)]
 pub fn album_crawl_main(
+    options: ArtistAlbumsOptions,
     client_ring: Arc<RwLock<ClientRing>>,
 ) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let (artist_sender, artist_receiver) = channel::unbounded();
     let (album_sender, album_receiver) = channel::unbounded();
 
+    telemetry.register_queue("album_crawl_input", artist_receiver.clone());
+    telemetry.register_queue("album_crawl_output", album_receiver.clone());
+
     let reader_thread = thread::spawn(move || {
         read_csv_into_sender(artist_sender, "artists_crawled.csv")
             .expect("Error in reading artists crawled")
     });
 
     let crawler_thread = thread::spawn(move || {
-        album_crawl(artist_receiver, client_ring, album_sender)
+        album_crawl(artist_receiver, options, client_ring, album_sender)
             .expect("Error in crawling tracks");
     });
 
@@ -193,4 +211,9 @@ pub fn album_crawl_main(
     writer_thread.join().unwrap_or_else(|err| {
         error!("Error in album writer thread: {:?}", err);
     });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("album_crawl").unwrap_or_else(|err| {
+        error!("Error in writing album_crawl client stats: {}", err);
+    });
 }
@@ -8,6 +8,13 @@ use serde_json::{
 };
 
 use crate::{
+    api_enums::{
+        AlbumGroup,
+        AlbumType,
+        CopyrightType,
+        ObjectType,
+        ReleaseDatePrecision,
+    },
     artist_types::{
         ArtistSimple,
     },
@@ -15,6 +22,13 @@ use crate::{
         Image,
         Paging,
     },
+    ids::{
+        AlbumId,
+        ArtistId,
+    },
+    release_date::{
+        ReleaseDate,
+    },
     track_types::{
         TrackSimple,
     },
@@ -24,23 +38,35 @@ macro_rules! with_album_core_fields {
     (pub struct $name:ident { $( pub $field:ident: $ty:ty ),* $(,)* }) => {
         #[derive(Debug, Deserialize, Serialize)]
         pub struct $name {
-            pub album_group: Option<String>,
-            pub album_type: String,
+            pub album_group: Option<AlbumGroup>,
+            pub album_type: AlbumType,
             pub artists: Vec<ArtistSimple>,
             pub available_markets: Option<Vec<String>>,
             pub external_urls: Map<String, Value>,
             pub href: String,
-            pub id: String,
+            pub id: AlbumId,
             pub images: Vec<Image>,
             pub name: String,
+            // kept as sent, see parsed_release_date
             pub release_date: String,
-            pub release_date_precision: String,
+            pub release_date_precision: ReleaseDatePrecision,
             pub restrictions: Option<Map<String, Value>>,
             pub uri: String,
             #[serde(rename = "type")] 
-            pub object_type: String,
+            pub object_type: ObjectType,
             $( pub $field: $ty ),*
         }
+
+        impl $name {
+            // None for dates the API sent in an unexpected format, which
+            // should not fail the whole page they came in.
+            #[allow(dead_code)]
+            pub fn parsed_release_date(
+                &self,
+            ) -> Option<ReleaseDate> {
+                ReleaseDate::parse(&self.release_date[..]).ok()
+            }
+        }
     };
 }
 
@@ -50,7 +76,7 @@ with_album_core_fields!(pub struct AlbumSimple {});
 pub struct Copyright {
     pub text: String,
     #[serde(rename = "type")]
-    pub object_type: String,
+    pub object_type: CopyrightType,
 }
 
 with_album_core_fields!(pub struct AlbumFull {
@@ -64,29 +90,51 @@ with_album_core_fields!(pub struct AlbumFull {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AlbumCsv {
-    pub origin_artist: String,
+    pub origin_artist: ArtistId,
     pub origin_artist_genres: String,
-    pub album_type: String,
-    pub id: String,
+    pub album_type: AlbumType,
+    // relative to origin_artist
+    pub album_group: Option<AlbumGroup>,
+    pub id: AlbumId,
     pub name: String,
     pub release_date: String,
-    pub release_date_precision: String,
+    pub release_date_precision: ReleaseDatePrecision,
+    // Derived from release_date when written and empty if it does not
+    // parse. Not read back, so files without these columns still load.
+    #[serde(skip_deserializing)]
+    pub release_year: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub release_month: Option<u32>,
+    #[serde(skip_deserializing)]
+    pub release_decade: Option<i32>,
 }
 
 impl AlbumCsv {
     pub fn extract_from(
         album_simple: AlbumSimple,
-        origin_artist: String,
+        origin_artist: ArtistId,
         origin_artist_genres: String,
     ) -> Self {
+        let release_date = album_simple.parsed_release_date();
         Self {
             origin_artist: origin_artist,
             origin_artist_genres: origin_artist_genres,
             album_type: album_simple.album_type,
+            album_group: album_simple.album_group,
             id: album_simple.id,
             name: album_simple.name,
             release_date: album_simple.release_date,
             release_date_precision: album_simple.release_date_precision,
+            release_year: release_date.map(|release_date| release_date.year()),
+            release_month: release_date.and_then(|release_date| release_date.month()),
+            release_decade: release_date.map(|release_date| release_date.decade()),
         }
     }
+
+    #[allow(dead_code)]
+    pub fn parsed_release_date(
+        &self,
+    ) -> Option<ReleaseDate> {
+        ReleaseDate::parse(&self.release_date[..]).ok()
+    }
 }
@@ -0,0 +1,251 @@
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+};
+
+use serde::{
+    de::{
+        self,
+        Deserializer,
+        Visitor,
+    },
+    ser::{
+        Serializer,
+    },
+    Deserialize,
+    Serialize,
+};
+
+// Categorical string fields. Names are matched case-insensitively, and names
+// the API added since fall back to Unknown rather than failing the decode.
+macro_rules! named_enum {
+    ($name:ident { $( $variant:ident => $canonical:expr ),* $(,)* }) => {
+        #[allow(dead_code)]
+        #[derive(Clone, Debug, Eq, Hash, PartialEq)]
+        pub enum $name {
+            $( $variant, )*
+            Unknown(String),
+        }
+
+        impl $name {
+            pub fn parse(
+                input: &str,
+            ) -> Self {
+                let input = input.trim();
+                $(
+                    if input.eq_ignore_ascii_case($canonical) {
+                        return $name::$variant;
+                    }
+                )*
+                $name::Unknown(input.to_string())
+            }
+
+            pub fn name(
+                &self,
+            ) -> &str {
+                match self {
+                    $( $name::$variant => $canonical, )*
+                    $name::Unknown(name) => &name[..],
+                }
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+                write!(formatter, "{}", self.name())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.name())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                let input = String::deserialize(deserializer)?;
+                Ok(Self::parse(&input[..]))
+            }
+        }
+    };
+}
+
+// Categorical integer fields. The API sends codes, our CSVs hold canonical
+// names, and both decode; codes the API added since fall back to Unknown.
+macro_rules! coded_enum {
+    ($name:ident { $( $variant:ident => ($code:expr, $canonical:expr) ),* $(,)* }) => {
+        #[allow(dead_code)]
+        #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+        pub enum $name {
+            $( $variant, )*
+            Unknown(i32),
+        }
+
+        impl $name {
+            pub fn from_code(
+                code: i32,
+            ) -> Self {
+                match code {
+                    $( code if code == $code => $name::$variant, )*
+                    code => $name::Unknown(code),
+                }
+            }
+
+            pub fn parse(
+                input: &str,
+            ) -> Option<Self> {
+                let input = input.trim();
+                $(
+                    if input.eq_ignore_ascii_case($canonical) {
+                        return Some($name::$variant);
+                    }
+                )*
+                input.parse::<i32>().ok().map(Self::from_code)
+            }
+
+            #[allow(dead_code)]
+            pub fn code(
+                &self,
+            ) -> i32 {
+                match self {
+                    $( $name::$variant => $code, )*
+                    $name::Unknown(code) => *code,
+                }
+            }
+
+            pub fn name(
+                &self,
+            ) -> String {
+                match self {
+                    $( $name::$variant => $canonical.to_string(), )*
+                    $name::Unknown(code) => code.to_string(),
+                }
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+                write!(formatter, "{}", self.name())
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.name()[..])
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                struct CodeVisitor;
+
+                impl<'de> Visitor<'de> for CodeVisitor {
+                    type Value = $name;
+
+                    fn expecting(&self, formatter: &mut Formatter) -> fmt::Result {
+                        write!(formatter, "a {} code or name", stringify!($name))
+                    }
+
+                    fn visit_i64<E: de::Error>(
+                        self,
+                        code: i64,
+                    ) -> Result<Self::Value, E> {
+                        Ok($name::from_code(code as i32))
+                    }
+
+                    fn visit_u64<E: de::Error>(
+                        self,
+                        code: u64,
+                    ) -> Result<Self::Value, E> {
+                        Ok($name::from_code(code as i32))
+                    }
+
+                    fn visit_str<E: de::Error>(
+                        self,
+                        input: &str,
+                    ) -> Result<Self::Value, E> {
+                        $name::parse(input).ok_or_else(|| {
+                            E::custom(format!("{} is not a {} code or name", input, stringify!($name)))
+                        })
+                    }
+                }
+
+                deserializer.deserialize_any(CodeVisitor)
+            }
+        }
+    };
+}
+
+// Relative to the artist the album was listed for.
+named_enum!(AlbumGroup {
+    Album => "album",
+    Single => "single",
+    Compilation => "compilation",
+    AppearsOn => "appears_on",
+});
+
+named_enum!(AlbumType {
+    Album => "album",
+    Single => "single",
+    Compilation => "compilation",
+});
+
+// C for copyright, P for sound recording (performance) copyright
+named_enum!(CopyrightType {
+    Copyright => "C",
+    Performance => "P",
+});
+
+// The type field every API object carries.
+named_enum!(ObjectType {
+    Album => "album",
+    Artist => "artist",
+    AudioFeatures => "audio_features",
+    Episode => "episode",
+    Playlist => "playlist",
+    Show => "show",
+    Track => "track",
+    User => "user",
+});
+
+named_enum!(ReleaseDatePrecision {
+    Year => "year",
+    Month => "month",
+    Day => "day",
+});
+
+// Pitch class of the estimated key, -1 when no key was detected.
+coded_enum!(Key {
+    NoKey => (-1, "none"),
+    C => (0, "C"),
+    CSharp => (1, "C#"),
+    D => (2, "D"),
+    DSharp => (3, "D#"),
+    E => (4, "E"),
+    F => (5, "F"),
+    FSharp => (6, "F#"),
+    G => (7, "G"),
+    GSharp => (8, "G#"),
+    A => (9, "A"),
+    ASharp => (10, "A#"),
+    B => (11, "B"),
+});
+
+coded_enum!(Mode {
+    Minor => (0, "minor"),
+    Major => (1, "major"),
+});
@@ -8,11 +8,17 @@ use std::{
 use futures::{
     Future,
 };
+use reqwest::{
+    Url,
+};
 
 use crate::{
     album_types::{
         AlbumSimple,
     },
+    api_enums::{
+        AlbumGroup,
+    },
     artist_types::{
         ArtistFull,
     },
@@ -20,23 +26,81 @@ use crate::{
         ClientRing,
     },
     common_types::{
+        ArtistsEnvelope,
         Paging,
+        TracksEnvelope,
+    },
+    ids::{
+        ArtistId,
+        join_ids,
+    },
+    market::{
+        Market,
+    },
+    search::{
+        search,
+        SearchQuery,
+        SearchType,
     },
     track_types::{
         TrackFull,
     },
     utils::{
+        ErrorKind,
+        get_field_with_retry,
+        get_item_lookups_with_retry,
         get_with_retry,
-        search,
+        ItemLookup,
         SimpleError,
     },
 };
 
 type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
 
+#[derive(Clone, Debug)]
+pub struct ArtistAlbumsOptions {
+    pub include_groups: Vec<AlbumGroup>,
+    // page size, at most 50
+    pub limit: Option<u32>,
+    pub market: Option<Market>,
+}
+
+// The artist's own releases, 20 per page, in no particular market.
+impl Default for ArtistAlbumsOptions {
+    fn default(
+    ) -> Self {
+        Self {
+            include_groups: vec![AlbumGroup::Album, AlbumGroup::Single, AlbumGroup::Compilation],
+            limit: None,
+            market: None,
+        }
+    }
+}
+
+impl ArtistAlbumsOptions {
+    fn url(
+        &self,
+        artist_id: &ArtistId,
+    ) -> String {
+        let mut params = Vec::new();
+        if !self.include_groups.is_empty() {
+            params.push(("include_groups", self.include_groups.iter().map(|album_group| {
+                album_group.name()
+            }).collect::<Vec<&str>>().join(",")));
+        }
+        self.limit.map(|limit| params.push(("limit", limit.to_string())));
+        self.market.as_ref().map(|market| params.push(("market", market.to_string())));
+
+        Url::parse_with_params(
+            &format!("https://api.spotify.com/v1/artists/{}/albums/", artist_id)[..],
+            params,
+        ).expect("Error in artist::ArtistAlbumsOptions::url format").into_string()
+    }
+}
+
 pub fn get_artist(
     client_ring: Arc<RwLock<ClientRing>>,
-    artist_id: String,
+    artist_id: ArtistId,
 ) -> CustomFuture<ArtistFull> {
     Box::new(
         get_with_retry(
@@ -48,11 +112,11 @@ pub fn get_artist(
 
 pub fn get_artist_albums(
     client_ring: Arc<RwLock<ClientRing>>,
-    artist_id: String,
+    (artist_id, options): (ArtistId, ArtistAlbumsOptions),
 ) -> CustomFuture<Paging<AlbumSimple>> {
     Box::new(
         get_with_retry(
-            format!("https://api.spotify.com/v1/artists/{}/albums/?include_groups=album,single,compilation&country=US", artist_id),
+            options.url(&artist_id),
             client_ring,
         )
     )
@@ -60,61 +124,35 @@ pub fn get_artist_albums(
 
 pub fn get_artist_top_tracks(
     client_ring: Arc<RwLock<ClientRing>>,
-    artist_id: String,
+    (artist_id, market): (ArtistId, Market),
 ) -> CustomFuture<Vec<TrackFull>> {
-    Box::new(
-        get_with_retry::<serde_json::Value>(
-            format!("https://api.spotify.com/v1/artists/{}/top-tracks/?country=US", artist_id),
-            client_ring,
-        ).map(|value| {
-            value.get("tracks").expect("Error in artist::get_artist_top_tracks format")
-                .as_array().expect("Error in artist::get_artist_top_tracks format")
-                .into_iter().map(|value| {
-                    serde_json::from_value::<TrackFull>(value.to_owned())
-                        .expect("Error in artist::get_artist_top_tracks format")
-                }).collect()
-        })
+    get_field_with_retry::<TracksEnvelope<_>>(
+        format!("https://api.spotify.com/v1/artists/{}/top-tracks/?market={}", artist_id, market),
+        client_ring,
     )
 }
 
 pub fn get_artist_related_artists(
     client_ring: Arc<RwLock<ClientRing>>,
-    artist_id: String,
+    artist_id: ArtistId,
 ) -> CustomFuture<Vec<ArtistFull>> {
-    Box::new(
-        get_with_retry::<serde_json::Value>(
-            format!("https://api.spotify.com/v1/artists/{}/related-artists/", artist_id),
-            client_ring,
-        ).map(|value| {
-            value.get("artists").expect("Error in artist::get_artist_related_artists format")
-                .as_array().expect("Error in artist::get_artist_related_artists format")
-                .into_iter().map(|value| {
-                    serde_json::from_value::<ArtistFull>(value.to_owned())
-                        .expect("Error in artist::get_artist_related_artists format")
-                }).collect()
-        })
+    get_field_with_retry::<ArtistsEnvelope<_>>(
+        format!("https://api.spotify.com/v1/artists/{}/related-artists/", artist_id),
+        client_ring,
     )
 }
 
 pub fn get_artists(
     client_ring: Arc<RwLock<ClientRing>>,
-    artist_ids: Vec<String>,
-) -> CustomFuture<Vec<ArtistFull>> {
-    Box::new(
-        get_with_retry::<serde_json::Value>(
-            format!(
-                "https://api.spotify.com/v1/artists/?ids={}",
-                artist_ids.join(",")
-            ),
-            client_ring,
-        ).map(|value| {
-            value.get("artists").expect("Error in artist::get_artists format")
-                .as_array().expect("Error in artist::get_artists format")
-                .iter().map(|value| {
-                    serde_json::from_value::<ArtistFull>(value.to_owned())
-                        .expect("Error in artist::get_artists format")
-                }).collect()
-        })
+    artist_ids: Vec<ArtistId>,
+) -> CustomFuture<Vec<ItemLookup<ArtistFull>>> {
+    get_item_lookups_with_retry::<_, ArtistsEnvelope<_>>(
+        format!(
+            "https://api.spotify.com/v1/artists/?ids={}",
+            join_ids(&artist_ids),
+        ),
+        artist_ids.len(),
+        client_ring,
     )
 }
 
@@ -123,12 +161,12 @@ pub fn search_artists(
     query: String,
 ) -> CustomFuture<Paging<ArtistFull>> {
     Box::new(
-        search::<serde_json::Value>(query, "artist", client_ring)
-            .map(|value| {
-                serde_json::from_value(
-                    value.get("artists").expect("Error in artist::search_artists format")
-                        .to_owned()
-                ).expect("Error in artist::search_artists format")
+        search(client_ring, SearchQuery::keywords(query, SearchType::Artist))
+            .and_then(|results| {
+                results.artists.ok_or(Box::new(SimpleError {
+                    message: "No artists in artist::search_artists results".to_string(),
+                    kind: ErrorKind::Permanent,
+                }))
             })
     )
 }
@@ -39,12 +39,14 @@ use tokio::{
 use crate::{
     artist::{
         get_artist_related_artists,
-        search_artists,
     },
     artist_types::{
         ArtistFull,
         ArtistCsv,
     },
+    browse::{
+        BrowseOptions,
+    },
     client::{
         ClientRing,
     },
@@ -52,7 +54,13 @@ use crate::{
         lines_from_file,
         write_csv_through_receiver,
     },
+    seed::{
+        resolve_seed_artists,
+        seeds_from_browse,
+        seeds_from_genres,
+    },
     utils::{
+        ErrorKind,
         loop_until_ok,
         SimpleError,
     },
@@ -66,6 +74,7 @@ fn crawl_related_artists(
 ) {
     let mut rt = Runtime::new().expect("No tokio runtime");
     
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let crawled = Arc::new(CHashMap::new());
     // let num_processed = Arc::new(AtomicUsize::new(0));
     let progress = Arc::new(ProgressBar::new(limit as u64));
@@ -89,6 +98,7 @@ fn crawl_related_artists(
         let progress_clone = progress.clone();
         let queue_clone = queue.clone();
         let sender_clone = sender.clone();
+        let telemetry_clone = telemetry.clone();
 
         Box::new(acc_future.join(future::ok(queue.pop()).and_then(|artist| {
             let artist_id_clone = artist.id.clone();
@@ -103,6 +113,7 @@ fn crawl_related_artists(
                     artist_id_clone,
                     err,
                 ),
+                kind: ErrorKind::Permanent,
             }.into()).map(move |vec| {
                 vec.into_iter().map(|artist_full| {
                     if !crawled_clone.contains_key(&artist_full.id) &&
@@ -121,6 +132,7 @@ fn crawl_related_artists(
                     );
                 });
                 progress_clone.inc(1);
+                telemetry_clone.record_processed("artist_crawl", 1);
             })
         })).map(|_| {
             ()
@@ -139,18 +151,54 @@ pub fn artist_crawl_main(
     limit: usize,
     client_ring: Arc<RwLock<ClientRing>>,
 ) {
-    let mut rt = Runtime::new().expect("No tokio runtime");
+    let seeds = lines_from_file("seed_artists.txt")
+        .expect("Error in reading seed artists");
+    let seed_artists = resolve_seed_artists(seeds, "seed_resolution.csv", client_ring.clone());
+
+    artist_crawl_from_seeds(seed_artists, limit, client_ring);
+}
+
+#[allow(dead_code)]
+pub fn artist_crawl_browse_main(
+    limit: usize,
+    options: BrowseOptions,
+    playlists_per_category: usize,
+    num_seeds: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let seed_artists = seeds_from_browse(
+        options,
+        playlists_per_category,
+        num_seeds,
+        client_ring.clone(),
+    );
+
+    artist_crawl_from_seeds(seed_artists, limit, client_ring);
+}
 
+#[allow(dead_code)]
+pub fn artist_crawl_genres_main(
+    limit: usize,
+    per_genre: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let genres = lines_from_file("seed_genres.txt")
+        .expect("Error in reading seed genres");
+    let seed_artists = seeds_from_genres(genres, per_genre, client_ring.clone());
+
+    artist_crawl_from_seeds(seed_artists, limit, client_ring);
+}
+
+pub fn artist_crawl_from_seeds(
+    seed_artists: Vec<ArtistFull>,
+    limit: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let (artist_sender, artist_receiver) = channel::unbounded();
 
-    let seed_artists: Vec<ArtistFull> = lines_from_file("seed_artists.txt")
-        .expect("Error in reading seed artists").into_iter().map(|name| {
-            rt.block_on(search_artists(client_ring.clone(), name).and_then(|mut vec| {
-                vec.items.drain(..).next().ok_or(SimpleError {
-                    message: "Empty seed artist".to_string(),
-                }.into())
-            })).expect("Error in searching artists")
-        }).collect();
+    info!("Crawling related artists from {} seeds", seed_artists.len());
+    telemetry.register_queue("artist_crawl_output", artist_receiver.clone());
 
     let crawler_thread = thread::spawn(move || {
         crawl_related_artists(
@@ -174,5 +222,10 @@ pub fn artist_crawl_main(
         error!("Error in artist writer thread: {:?}", err);
     });
 
+    telemetry.log_summary();
+    telemetry.dump_stage("artist_crawl").unwrap_or_else(|err| {
+        error!("Error in writing artist_crawl client stats: {}", err);
+    });
+
     // info!("fdjkas");
 }
@@ -8,9 +8,15 @@ use serde_json::{
 };
 
 use crate::{
+    api_enums::{
+        ObjectType,
+    },
     common_types::{
         Image,
     },
+    ids::{
+        ArtistId,
+    },
 };
 
 macro_rules! with_artist_core_fields {
@@ -19,11 +25,11 @@ macro_rules! with_artist_core_fields {
         pub struct $name {
             pub external_urls: Map<String, Value>,
             pub href: String,
-            pub id: String,
+            pub id: ArtistId,
             pub name: String,
             pub uri: String,
             #[serde(rename = "type")] 
-            pub object_type: String,
+            pub object_type: ObjectType,
             $( pub $field: $ty ),*
         }
     };
@@ -33,8 +39,8 @@ with_artist_core_fields!(pub struct ArtistSimple {});
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Followers {
-    href: Option<String>,
-    total: i32,
+    pub href: Option<String>,
+    pub total: i32,
 }
 
 with_artist_core_fields!(pub struct ArtistFull {
@@ -46,7 +52,7 @@ with_artist_core_fields!(pub struct ArtistFull {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct ArtistCsv {
-    pub id: String,
+    pub id: ArtistId,
     pub name: String,
     pub followers_total: i32,
     pub genres: String,
@@ -0,0 +1,109 @@
+use std::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use futures::{
+    Future,
+};
+use reqwest::{
+    Url,
+};
+
+use crate::{
+    album_types::{
+        AlbumSimple,
+    },
+    browse_types::{
+        Category,
+        FeaturedPlaylists,
+    },
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        AlbumsEnvelope,
+        CategoriesEnvelope,
+        Paging,
+        PlaylistsEnvelope,
+    },
+    playlist_types::{
+        PlaylistSimple,
+    },
+    utils::{
+        get_field_with_retry,
+        get_with_retry,
+        SimpleError,
+    },
+};
+
+type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
+
+#[derive(Clone, Debug, Default)]
+pub struct BrowseOptions {
+    // ISO 3166-1 alpha-2 country code, e.g. "US"
+    pub country: Option<String>,
+    // ISO 639-1 language code and country code, e.g. "es_MX"
+    pub locale: Option<String>,
+    pub limit: Option<u32>,
+}
+
+impl BrowseOptions {
+    fn url(
+        &self,
+        path: &str,
+    ) -> String {
+        let mut params = Vec::new();
+        self.country.as_ref().map(|country| params.push(("country", country.clone())));
+        self.locale.as_ref().map(|locale| params.push(("locale", locale.clone())));
+        self.limit.map(|limit| params.push(("limit", limit.to_string())));
+
+        Url::parse_with_params(&format!("https://api.spotify.com/v1/browse/{}", path)[..], params)
+            .expect("Error in browse::BrowseOptions::url format")
+            .into_string()
+    }
+}
+
+pub fn get_categories(
+    client_ring: Arc<RwLock<ClientRing>>,
+    options: BrowseOptions,
+) -> CustomFuture<Paging<Category>> {
+    get_field_with_retry::<CategoriesEnvelope<_>>(
+        options.url("categories/"),
+        client_ring,
+    )
+}
+
+pub fn get_category_playlists(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (category_id, options): (String, BrowseOptions),
+) -> CustomFuture<Paging<PlaylistSimple>> {
+    get_field_with_retry::<PlaylistsEnvelope<_>>(
+        options.url(&format!("categories/{}/playlists/", category_id)[..]),
+        client_ring,
+    )
+}
+
+pub fn get_new_releases(
+    client_ring: Arc<RwLock<ClientRing>>,
+    options: BrowseOptions,
+) -> CustomFuture<Paging<AlbumSimple>> {
+    get_field_with_retry::<AlbumsEnvelope<_>>(
+        options.url("new-releases/"),
+        client_ring,
+    )
+}
+
+pub fn get_featured_playlists(
+    client_ring: Arc<RwLock<ClientRing>>,
+    options: BrowseOptions,
+) -> CustomFuture<FeaturedPlaylists> {
+    Box::new(
+        get_with_retry(
+            options.url("featured-playlists/"),
+            client_ring,
+        )
+    )
+}
@@ -0,0 +1,28 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    common_types::{
+        Image,
+        Paging,
+    },
+    playlist_types::{
+        PlaylistSimple,
+    },
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Category {
+    pub href: String,
+    pub icons: Vec<Image>,
+    pub id: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct FeaturedPlaylists {
+    pub message: Option<String>,
+    pub playlists: Paging<PlaylistSimple>,
+}
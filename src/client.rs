@@ -4,6 +4,7 @@ use std::{
     },
     collections::{
         HashMap,
+        HashSet,
     },
     error::{
         Error,
@@ -11,8 +12,12 @@ use std::{
     net::{
         Ipv4Addr,
     },
+    path::{
+        PathBuf,
+    },
     sync::{
         Arc,
+        Mutex,
     },
     thread::{
         self,
@@ -49,24 +54,43 @@ use tokio::{
 };
 
 use crate::{
+    credentials::{
+        CredentialSource,
+        Secret,
+        SpotifyClientMetadata,
+    },
+    decode::{
+        DecodeContext,
+    },
     io::{
         structs_from_file,
     },
+    telemetry::{
+        Telemetry,
+    },
+    token_cache::{
+        TokenCache,
+    },
+    user_auth::{
+        refresh_stored_user_token,
+        user_token_for,
+        UserToken,
+    },
 };
 
 #[derive(Debug, Deserialize, Serialize)]
 struct AccessToken {
-    pub access_token: String,
+    pub access_token: Secret,
     pub token_type: String,
     pub expires_in: i32,
     pub scope: String,
 }
 
-#[derive(Clone, Debug, Deserialize, Serialize)]
-struct SpotifyClientMetadata {
-    pub name: String,
-    pub id: String,
-    pub secret: String,
+#[derive(Clone, Debug)]
+enum Grant {
+    ClientCredentials,
+    // user-scoped token, refreshed through the token file it is stored in
+    AuthorizationCode(String),
 }
 
 #[derive(Clone, Debug)]
@@ -74,7 +98,23 @@ struct SpotifyClientWithProxy {
     pub client_metadata: SpotifyClientMetadata,
     pub client: Client,
     pub proxy: Option<Proxy>,
-    pub token: String,
+    pub token: Secret,
+    pub grant: Grant,
+}
+
+fn build_proxy_client(
+    client_metadata: &SpotifyClientMetadata,
+    proxy_opt: &Option<Proxy>,
+) -> reqwest::Result<Client> {
+    let builder = proxy_opt.clone().map(|proxy| -> reqwest::Result<ClientBuilder> {
+        let proxy_netloc = format!("http://{}:{}", proxy.ip_address, proxy.port);
+        info!("Using {} for {} client", proxy_netloc, client_metadata.name);
+        Ok(
+            Client::builder().proxy(reqwest::Proxy::all(&proxy_netloc[..])?)
+        )
+    }).unwrap_or(Ok(Client::builder()))?;
+
+    builder.build()
 }
 
 impl SpotifyClientWithProxy {
@@ -82,25 +122,32 @@ impl SpotifyClientWithProxy {
         token_client: &Client,
         client_metadata: SpotifyClientMetadata,
         proxy_opt: Option<Proxy>,
+        token_cache: &mut TokenCache,
     ) -> reqwest::Result<Self> {
-        let builder = proxy_opt.clone().map(|proxy| -> reqwest::Result<ClientBuilder> {
-            let proxy_netloc = format!("http://{}:{}", proxy.ip_address, proxy.port);
-            info!("Using {} for {} client", proxy_netloc, client_metadata.name);
-            Ok(
-                Client::builder().proxy(reqwest::Proxy::all(&proxy_netloc[..])?)
-            )
-        }).unwrap_or(Ok(Client::builder()))?;
-            
-        let proxy_client = builder.build()?;
-
-        info!("Retrieving API token for {} client", client_metadata.name);
-
-        let mut rt = Runtime::new().expect("No tokio runtime");
-        let token = rt.block_on(retrieve_access_token(
-            token_client,
-            &client_metadata.id[..],
-            &client_metadata.secret[..],
-        ))?.access_token;
+        let proxy_client = build_proxy_client(&client_metadata, &proxy_opt)?;
+
+        let token = match token_cache.get(&client_metadata.id[..]) {
+            Some(token) => {
+                info!("Reusing cached API token for {} client", client_metadata.name);
+                token
+            },
+            None => {
+                info!("Retrieving API token for {} client", client_metadata.name);
+
+                let mut rt = Runtime::new().expect("No tokio runtime");
+                let access_token = rt.block_on(retrieve_access_token(
+                    token_client,
+                    &client_metadata.id[..],
+                    &client_metadata.secret,
+                ))?;
+                token_cache.insert(
+                    &client_metadata.id[..],
+                    access_token.access_token.clone(),
+                    access_token.expires_in,
+                );
+                access_token.access_token
+            },
+        };
         info!("Using token {} for {} client", token, client_metadata.name);
 
         Ok(Self {
@@ -108,8 +155,39 @@ impl SpotifyClientWithProxy {
             client: proxy_client,
             proxy: proxy_opt,
             token: token,
+            grant: Grant::ClientCredentials,
         })
     }
+
+    pub fn init_user(
+        client_metadata: SpotifyClientMetadata,
+        proxy_opt: Option<Proxy>,
+        user_token: UserToken,
+        token_file: &str,
+    ) -> reqwest::Result<Self> {
+        let proxy_client = build_proxy_client(&client_metadata, &proxy_opt)?;
+        info!("Using user token {} for {} client", user_token.access_token, client_metadata.name);
+
+        Ok(Self {
+            client_metadata: client_metadata,
+            client: proxy_client,
+            proxy: proxy_opt,
+            token: user_token.access_token,
+            grant: Grant::AuthorizationCode(token_file.to_string()),
+        })
+    }
+
+    pub fn key(
+        &self,
+    ) -> String {
+        format!(
+            "{}@{}",
+            self.client_metadata.name,
+            self.proxy.as_ref().map(|proxy| {
+                format!("{}:{}", proxy.ip_address, proxy.port)
+            }).unwrap_or("direct".to_string()),
+        )
+    }
 }
 
 #[derive(Clone, Debug, Deserialize, Serialize)]
@@ -120,17 +198,25 @@ struct Proxy {
 
 pub struct ClientRing {
     token_client: Client,
+    token_cache: TokenCache,
+    telemetry: Arc<Telemetry>,
     current_client: SpotifyClientWithProxy,
     client_ring: Arc<AtomicRingQueue<SpotifyClientWithProxy>>,
     proxies: Arc<AtomicRingQueue<Option<Proxy>>>,
+    // where undecodable response bodies are dumped, if anywhere
+    diagnostics_dir: Option<PathBuf>,
+    // URLs whose bodies were dumped already, shared by every decode context
+    dumped_urls: Arc<Mutex<HashSet<String>>>,
 }
 
 impl ClientRing {
     pub fn init(
         token_client: Client,
+        credential_source: CredentialSource,
+        mut token_cache: TokenCache,
         use_proxies: bool,
     ) -> Result<Self, Box<dyn Error>> {
-        let clients_metadata = structs_from_file::<SpotifyClientMetadata>("clients.csv")?;
+        let clients_metadata = credential_source.load()?;
         let clients_metadata_len = clients_metadata.len();
 
         let mut proxies = vec![None];
@@ -143,8 +229,11 @@ impl ClientRing {
         let mut clients_with_proxies = clients_metadata.into_iter().zip(
             proxies.iter().cloned().cycle().take(clients_metadata_len),
         ).map(|(client_metadata, proxy)| {
-            SpotifyClientWithProxy::init(&token_client, client_metadata, proxy)
+            SpotifyClientWithProxy::init(&token_client, client_metadata, proxy, &mut token_cache)
         }).collect::<reqwest::Result<Vec<SpotifyClientWithProxy>>>()?;
+        token_cache.save().unwrap_or_else(|err| {
+            warn!("Error in saving token cache: {}", err);
+        });
 
         let current_client = clients_with_proxies.pop().expect("Empty clients or proxies");
         let client_ring = Arc::new(AtomicRingQueue::with_capacity(clients_metadata_len * 2));
@@ -157,18 +246,94 @@ impl ClientRing {
         ));
         proxies.into_iter().map(|proxy| { proxies_queue.push_overwrite(proxy); }).last();
 
+        let telemetry = Arc::new(Telemetry::new());
+        telemetry.set_ring_clients(clients_metadata_len);
+
         Ok(Self {
             token_client: token_client,
+            token_cache: token_cache,
+            telemetry: telemetry,
             current_client: current_client,
             client_ring: client_ring,
             proxies: proxies_queue,
+            diagnostics_dir: None,
+            dumped_urls: Arc::new(Mutex::new(HashSet::new())),
+        })
+    }
+
+    // A ring holding a single user-authorized client, for endpoints that need
+    // user scopes. Coexists with client-credentials rings built by init.
+    #[allow(dead_code)]
+    pub fn init_user(
+        token_client: Client,
+        client_metadata: SpotifyClientMetadata,
+        scopes: Vec<String>,
+        port: u16,
+        token_file: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let user_token = user_token_for(
+            &token_client,
+            &client_metadata.id[..],
+            &scopes[..],
+            port,
+            token_file,
+        )?;
+        let current_client = SpotifyClientWithProxy::init_user(
+            client_metadata,
+            None,
+            user_token,
+            token_file,
+        )?;
+
+        let proxies_queue = Arc::new(AtomicRingQueue::with_capacity(2));
+        proxies_queue.push_overwrite(None);
+
+        let telemetry = Arc::new(Telemetry::new());
+        telemetry.set_ring_clients(1);
+
+        Ok(Self {
+            token_client: token_client,
+            token_cache: TokenCache::in_memory(),
+            telemetry: telemetry,
+            current_client: current_client,
+            client_ring: Arc::new(AtomicRingQueue::with_capacity(2)),
+            proxies: proxies_queue,
+            diagnostics_dir: None,
+            dumped_urls: Arc::new(Mutex::new(HashSet::new())),
         })
     }
 
     pub fn front(
         &self,
-    ) -> (Client, String) {
-        (self.current_client.client.clone(), self.current_client.token.clone())
+    ) -> (Client, Secret, String) {
+        (
+            self.current_client.client.clone(),
+            self.current_client.token.clone(),
+            self.current_client.key(),
+        )
+    }
+
+    pub fn telemetry(
+        &self,
+    ) -> Arc<Telemetry> {
+        self.telemetry.clone()
+    }
+
+    pub fn decode_context(
+        &self,
+    ) -> DecodeContext {
+        DecodeContext {
+            diagnostics_dir: self.diagnostics_dir.clone(),
+            dumped_urls: self.dumped_urls.clone(),
+            schema_drift: self.telemetry.schema_drift(),
+        }
+    }
+
+    pub fn set_diagnostics_dir(
+        &mut self,
+        diagnostics_dir: Option<PathBuf>,
+    ) {
+        self.diagnostics_dir = diagnostics_dir;
     }
 
     pub fn sleep_front_and_get_next(
@@ -176,13 +341,17 @@ impl ClientRing {
         secs: u64,
     ) {
         info!("Sleeping {} client {} seconds", self.current_client.client_metadata.name, secs);
+        self.telemetry.record_cooldown(&self.current_client.key()[..], secs);
+        self.telemetry.cooldown_started();
 
         let ring_clone = self.client_ring.clone();
         let current_client_clone = self.current_client.clone();
+        let telemetry_clone = self.telemetry.clone();
         thread::spawn(move || {
             sleep(Duration::from_secs(secs));
             ring_clone.try_push(current_client_clone)
                 .expect("Error in pushing new client in sleep");
+            telemetry_clone.cooldown_finished();
         });
 
         self.current_client = self.client_ring.pop();
@@ -197,11 +366,35 @@ impl ClientRing {
         self.proxies.try_push(self.current_client.proxy.clone())
             .expect("Error in pushing new proxy in refresh");
 
-        self.client_ring.try_push(SpotifyClientWithProxy::init(
-            &self.token_client,
-            self.current_client.client_metadata.clone(),
-            self.proxies.pop(),
-        ).expect("Error in refreshing client")).expect("Error in pushing new client in refresh");
+        let refreshed_client = match self.current_client.grant.clone() {
+            Grant::ClientCredentials => {
+                self.token_cache.remove(&self.current_client.client_metadata.id[..]);
+                let refreshed_client = SpotifyClientWithProxy::init(
+                    &self.token_client,
+                    self.current_client.client_metadata.clone(),
+                    self.proxies.pop(),
+                    &mut self.token_cache,
+                ).expect("Error in refreshing client");
+                self.token_cache.save().unwrap_or_else(|err| {
+                    warn!("Error in saving token cache: {}", err);
+                });
+                refreshed_client
+            },
+            Grant::AuthorizationCode(token_file) => {
+                let user_token = refresh_stored_user_token(
+                    &self.token_client,
+                    &self.current_client.client_metadata.id[..],
+                    &token_file[..],
+                ).expect("Error in refreshing user token");
+                SpotifyClientWithProxy::init_user(
+                    self.current_client.client_metadata.clone(),
+                    self.proxies.pop(),
+                    user_token,
+                    &token_file[..],
+                ).expect("Error in refreshing client")
+            },
+        };
+        self.client_ring.try_push(refreshed_client).expect("Error in pushing new client in refresh");
 
         self.current_client = self.client_ring.pop();
         info!("Using {} client", self.current_client.client_metadata.name);
@@ -211,13 +404,13 @@ impl ClientRing {
 fn retrieve_access_token(
     client: &Client,
     id: &str,
-    secret: &str,
+    secret: &Secret,
 ) -> impl Future<Item = AccessToken, Error = reqwest::Error> {
     let mut form_data = HashMap::new();
     form_data.insert("grant_type", "client_credentials");
     
     client.post("https://accounts.spotify.com/api/token/")
-        .basic_auth(id, Some(secret))
+        .basic_auth(id, Some(secret.expose()))
         .form(&form_data)
         .send().and_then(|mut response| {
             response.json()
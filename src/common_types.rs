@@ -3,11 +3,17 @@ use serde::{
     Serialize,
 };
 
+use crate::{
+    market::{
+        Market,
+    },
+};
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct Image {
-    pub height: i32,
+    pub height: Option<i32>,
     pub url: String,
-    pub width: i32,
+    pub width: Option<i32>,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -20,3 +26,79 @@ pub struct Paging<Item> {
     pub previous: Option<String>,
     pub total: i32,
 }
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Cursors {
+    pub after: Option<String>,
+    pub before: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct CursorPaging<Item> {
+    pub href: String,
+    pub items: Vec<Item>,
+    pub limit: i32,
+    pub next: Option<String>,
+    pub cursors: Option<Cursors>,
+    pub total: Option<i32>,
+}
+
+// A requested ID a multiple-item endpoint had nothing usable for, one row per
+// ID and market in each stage's *_missing.csv.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct MissingItemCsv {
+    pub id: String,
+    pub market: Option<Market>,
+    // not_found, malformed or request_failed, or analysis_not_found or
+    // analysis_malformed for tracks with features
+    pub status: String,
+    pub error: String,
+}
+
+// Some endpoints wrap their result in an object, e.g. {"artists": [...]}.
+// Envelopes let that result be deserialized straight from the body.
+pub trait Envelope {
+    type Inner;
+
+    const KEY: &'static str;
+
+    fn into_inner(
+        self,
+    ) -> Self::Inner;
+}
+
+macro_rules! envelope {
+    ($name:ident, $key:ident) => {
+        #[derive(Debug, Deserialize)]
+        pub struct $name<T> {
+            pub $key: T,
+        }
+
+        impl<T> Envelope for $name<T> {
+            type Inner = T;
+
+            const KEY: &'static str = stringify!($key);
+
+            fn into_inner(
+                self,
+            ) -> T {
+                self.$key
+            }
+        }
+    };
+}
+
+envelope!(AlbumsEnvelope, albums);
+envelope!(ArtistsEnvelope, artists);
+envelope!(AudioFeaturesEnvelope, audio_features);
+envelope!(CategoriesEnvelope, categories);
+envelope!(EpisodesEnvelope, episodes);
+envelope!(GenresEnvelope, genres);
+envelope!(PlaylistsEnvelope, playlists);
+envelope!(TracksEnvelope, tracks);
+
+// Stand-in for an object of which only the ID is needed.
+#[derive(Debug, Deserialize)]
+pub struct ObjectId<I> {
+    pub id: I,
+}
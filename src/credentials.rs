@@ -0,0 +1,175 @@
+use std::{
+    env,
+    error::{
+        Error,
+    },
+    fmt::{
+        Debug,
+        Display,
+        Formatter,
+        self,
+    },
+    fs,
+    io,
+};
+
+use csv::{
+    Reader,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    io::{
+        structs_from_file,
+    },
+    utils::{
+        ErrorKind,
+        SimpleError,
+    },
+};
+
+// Debug and Display never print the wrapped value; serialization does, so
+// credential and token files still round-trip.
+#[derive(Clone, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Secret(String);
+
+impl Secret {
+    pub fn new(
+        value: String,
+    ) -> Self {
+        Secret(value)
+    }
+
+    pub fn expose(
+        &self,
+    ) -> &str {
+        &self.0[..]
+    }
+}
+
+impl Debug for Secret {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "Secret(<redacted>)")
+    }
+}
+
+impl Display for Secret {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "<redacted>")
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SpotifyClientMetadata {
+    pub name: String,
+    pub id: String,
+    pub secret: Secret,
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub enum CredentialSource {
+    // plaintext CSV with name,id,secret columns (the old clients.csv)
+    ClientsFile(String),
+    // same format, but refused unless only the owner can read it
+    SecretsFile(String),
+    // SPOTIFY_CLIENT_ID/SPOTIFY_CLIENT_SECRET, or SPOTIFY_CLIENT_ID_0.. for several
+    Environment,
+    // CSV with name,id,secret columns piped in
+    Stdin,
+}
+
+impl CredentialSource {
+    pub fn load(
+        &self,
+    ) -> Result<Vec<SpotifyClientMetadata>, Box<dyn Error>> {
+        let clients_metadata = match self {
+            CredentialSource::ClientsFile(file_name) => {
+                check_permissions(file_name).unwrap_or_else(|err| {
+                    warn!("{}", err);
+                });
+                structs_from_file::<SpotifyClientMetadata>(&file_name[..])?
+            },
+            CredentialSource::SecretsFile(file_name) => {
+                check_permissions(file_name)?;
+                structs_from_file::<SpotifyClientMetadata>(&file_name[..])?
+            },
+            CredentialSource::Environment => clients_from_env(),
+            CredentialSource::Stdin => {
+                Reader::from_reader(io::stdin()).deserialize::<SpotifyClientMetadata>()
+                    .collect::<csv::Result<Vec<SpotifyClientMetadata>>>()?
+            },
+        };
+
+        if clients_metadata.is_empty() {
+            return Err(SimpleError {
+                message: format!("No client credentials found in {:?}", self),
+                kind: ErrorKind::Permanent,
+            }.into());
+        }
+        info!("Loaded {} client credentials from {:?}", clients_metadata.len(), self);
+
+        Ok(clients_metadata)
+    }
+}
+
+fn clients_from_env(
+) -> Vec<SpotifyClientMetadata> {
+    let mut clients_metadata = Vec::new();
+
+    if let (Ok(id), Ok(secret)) = (env::var("SPOTIFY_CLIENT_ID"), env::var("SPOTIFY_CLIENT_SECRET")) {
+        clients_metadata.push(SpotifyClientMetadata {
+            name: env::var("SPOTIFY_CLIENT_NAME").unwrap_or("env".to_string()),
+            id: id,
+            secret: Secret::new(secret),
+        });
+    }
+
+    let mut index = 0;
+    while let (Ok(id), Ok(secret)) = (
+        env::var(format!("SPOTIFY_CLIENT_ID_{}", index)),
+        env::var(format!("SPOTIFY_CLIENT_SECRET_{}", index)),
+    ) {
+        clients_metadata.push(SpotifyClientMetadata {
+            name: env::var(format!("SPOTIFY_CLIENT_NAME_{}", index))
+                .unwrap_or(format!("env{}", index)),
+            id: id,
+            secret: Secret::new(secret),
+        });
+        index += 1;
+    }
+
+    clients_metadata
+}
+
+#[cfg(unix)]
+pub fn check_permissions(
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let mode = fs::metadata(file_name)?.permissions().mode();
+    if mode & 0o077 != 0 {
+        return Err(SimpleError {
+            message: format!(
+                "{} is accessible by group or others (mode {:o}), expected 600",
+                file_name,
+                mode & 0o777,
+            ),
+            kind: ErrorKind::Permanent,
+        }.into());
+    }
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn check_permissions(
+    _file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    Ok(())
+}
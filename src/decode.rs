@@ -0,0 +1,272 @@
+use std::{
+    any::{
+        type_name,
+    },
+    collections::{
+        HashSet,
+    },
+    fs::{
+        self,
+    },
+    io::{
+        Read,
+    },
+    path::{
+        PathBuf,
+    },
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+        Mutex,
+    },
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use serde::{
+    de::{
+        DeserializeOwned,
+        DeserializeSeed,
+        Deserializer,
+    },
+};
+use serde_json::{
+    Value,
+};
+use serde_ignored;
+use serde_path_to_error;
+
+use crate::{
+    schema_drift::{
+        DriftKind,
+        FieldProbe,
+        SchemaDrift,
+        short_type_name,
+    },
+    utils::{
+        ErrorKind,
+        SimpleError,
+    },
+};
+
+// how much of an undecodable body goes into the error message
+const BODY_EXCERPT_LEN: usize = 500;
+
+// distinct URLs dumped per client ring, after which failures are only logged
+const MAX_DUMPED_URLS: usize = 1000;
+
+static DUMP_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub fn excerpt(
+    body: &[u8],
+) -> String {
+    if body.len() <= BODY_EXCERPT_LEN {
+        String::from_utf8_lossy(body).into_owned()
+    } else {
+        format!(
+            "{}... ({} bytes)",
+            String::from_utf8_lossy(&body[..BODY_EXCERPT_LEN]),
+            body.len(),
+        )
+    }
+}
+
+// Writes the body to <diagnostics_dir>/<millis>-<counter>-<url path>.json so
+// it can be turned into a fixture later.
+fn dump_payload(
+    body: &[u8],
+    url: &str,
+    diagnostics_dir: &PathBuf,
+) -> Option<PathBuf> {
+    let millis = SystemTime::now().duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis()).unwrap_or(0);
+    let url_path: String = url.trim_start_matches("https://api.spotify.com/v1/")
+        .split('?').next().unwrap_or("")
+        .chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .take(80).collect();
+    let file_path = diagnostics_dir.join(format!(
+        "{}-{}-{}.json",
+        millis,
+        DUMP_COUNTER.fetch_add(1, Ordering::SeqCst),
+        url_path.trim_matches('_'),
+    ));
+
+    fs::create_dir_all(diagnostics_dir).and_then(|_| {
+        fs::write(&file_path, body)
+    }).map(|_| file_path).map_err(|err| {
+        error!("Error in dumping undecodable body of {}: {}", url, err);
+    }).ok()
+}
+
+fn decode_error(
+    reason: String,
+    body: &[u8],
+    url: &str,
+    context: &DecodeContext,
+) -> Box<SimpleError> {
+    // A URL failing again, e.g. in a later crawl round, is not dumped again,
+    // unless its earlier dump could not be written. The lock is held while
+    // dumping so two threads don't dump the same URL.
+    let dumped = context.diagnostics_dir.as_ref().and_then(|diagnostics_dir| {
+        let mut dumped_urls = context.dumped_urls.lock().expect("dumped URLs Mutex poisoned");
+        if dumped_urls.contains(url) || dumped_urls.len() >= MAX_DUMPED_URLS {
+            return None;
+        }
+        dump_payload(body, url, diagnostics_dir).map(|file_path| {
+            dumped_urls.insert(url.to_string());
+            file_path
+        })
+    }).map(|file_path| format!(" (dumped to {})", file_path.display())).unwrap_or_default();
+
+    Box::new(SimpleError {
+        message: format!("Error in decoding {}: {}{}; body: {}", url, reason, dumped, excerpt(body)),
+        kind: ErrorKind::Permanent,
+    })
+}
+
+// Where decoding failures are dumped and schema drift is tallied, taken from
+// the client ring.
+#[derive(Clone, Default)]
+pub struct DecodeContext {
+    pub diagnostics_dir: Option<PathBuf>,
+    pub schema_drift: Option<Arc<SchemaDrift>>,
+    pub dumped_urls: Arc<Mutex<HashSet<String>>>,
+}
+
+fn join_path(
+    prefix: &str,
+    path: String,
+) -> String {
+    match path {
+        ref path if path == "." && prefix.is_empty() => path.clone(),
+        ref path if path == "." => prefix.to_string(),
+        ref path if prefix.is_empty() || path.starts_with('[') => format!("{}{}", prefix, path),
+        path => format!("{}.{}", prefix, path),
+    }
+}
+
+// serde_ignored paths look like artists.3.?.genres, with ? for Some
+fn ignored_path(
+    path: &serde_ignored::Path,
+) -> String {
+    path.to_string().split('.').filter(|segment| *segment != "?").fold(String::new(), |mut joined, segment| {
+        if segment.chars().all(|c| c.is_ascii_digit()) {
+            joined.push_str(&format!("[{}]", segment)[..]);
+        } else {
+            if !joined.is_empty() {
+                joined.push('.');
+            }
+            joined.push_str(segment);
+        }
+        joined
+    })
+}
+
+// Deserializes keeping track of the path for error messages. Errors are
+// "at <path>: <error>".
+fn deserialize_tracked<'de, De: Deserializer<'de>, D: DeserializeOwned>(
+    deserializer: De,
+    prefix: &str,
+) -> Result<D, String> {
+    serde_path_to_error::deserialize(deserializer).map_err(|err| {
+        format!("at {}: {}", join_path(prefix, err.path().to_string()), err.inner())
+    })
+}
+
+// As deserialize_tracked, also tallying fields our types ignore, fields they
+// expect but the body lacks, and the null failing the decode, if any.
+fn deserialize_drift_tracked<D: DeserializeOwned>(
+    value: &Value,
+    prefix: &str,
+    url: &str,
+    schema_drift: &SchemaDrift,
+) -> Result<D, String> {
+    let root_type = short_type_name(type_name::<D>());
+    let mut record_unexpected = |path: serde_ignored::Path| {
+        let path = join_path(prefix, ignored_path(&path));
+        schema_drift.record(&root_type[..], DriftKind::Unexpected, &path[..], url);
+    };
+
+    serde_path_to_error::deserialize(serde_ignored::Deserializer::new(
+        FieldProbe::new(value, prefix, &root_type[..], url, schema_drift),
+        &mut record_unexpected,
+    )).map_err(|err| {
+        let path = join_path(prefix, err.path().to_string());
+        let inner = err.inner().to_string();
+        if inner.starts_with("invalid type: null") {
+            schema_drift.record(&root_type[..], DriftKind::NullWhereRequired, &path[..], url);
+        }
+        format!("at {}: {}", path, inner)
+    })
+}
+
+pub fn decode_body<D: DeserializeOwned>(
+    body: &[u8],
+    url: &str,
+    context: &DecodeContext,
+) -> Result<D, Box<SimpleError>> {
+    match context.schema_drift {
+        Some(ref schema_drift) => serde_json::from_slice::<Value>(body).map_err(|err| err.to_string()).and_then(|value| {
+            deserialize_drift_tracked(&value, "", url, schema_drift)
+        }),
+        None => deserialize_tracked(&mut serde_json::Deserializer::from_slice(body), ""),
+    }.map_err(|reason| {
+        decode_error(reason, body, url, context)
+    })
+}
+
+// For types decoded through a seed while the body streams in. These neither
+// track paths nor tally schema drift, and as the body is never held whole,
+// failures give the line and column instead of an excerpt or a dump.
+pub fn decode_reader_seed<'de, R: Read, S: DeserializeSeed<'de>>(
+    seed: S,
+    reader: R,
+    url: &str,
+) -> Result<S::Value, Box<SimpleError>> {
+    let mut deserializer = serde_json::Deserializer::from_reader(reader);
+    seed.deserialize(&mut deserializer).and_then(|value| {
+        deserializer.end().map(|_| value)
+    }).map_err(|err| Box::new(SimpleError {
+        message: format!("Error in decoding {}: {}", url, err),
+        kind: ErrorKind::Permanent,
+    }))
+}
+
+// Decodes part of an already parsed body, with paths starting at prefix,
+// e.g. "artists[3]".
+pub fn decode_at<D: DeserializeOwned>(
+    value: &Value,
+    prefix: &str,
+    url: &str,
+    context: &DecodeContext,
+) -> Result<D, Box<SimpleError>> {
+    match context.schema_drift {
+        Some(ref schema_drift) => deserialize_drift_tracked(value, prefix, url, schema_drift),
+        None => deserialize_tracked(value, prefix),
+    }.map_err(|reason| {
+        let body = serde_json::to_vec(value).unwrap_or_default();
+        decode_error(reason, &body[..], url, context)
+    })
+}
+
+// For bodies wrapping the interesting part in an object, e.g.
+// {"artists": [...]}.
+pub fn decode_field<D: DeserializeOwned>(
+    value: &Value,
+    key: &str,
+    url: &str,
+    context: &DecodeContext,
+) -> Result<D, Box<SimpleError>> {
+    match value.get(key) {
+        Some(field) => decode_at(field, key, url, context),
+        None => {
+            let body = serde_json::to_vec(value).unwrap_or_default();
+            Err(decode_error(format!("no {} field", key), &body[..], url, context))
+        },
+    }
+}
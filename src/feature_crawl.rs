@@ -0,0 +1,321 @@
+use std::{
+    collections::{
+        HashSet,
+    },
+    sync::{
+        Arc,
+        RwLock,
+    },
+    thread,
+};
+
+use crossbeam_channel::{
+    self as channel,
+    Receiver,
+    Sender,
+};
+use futures::{
+    future,
+    Future,
+};
+use indicatif::{
+    ProgressBar,
+    ProgressStyle,
+};
+use num_cpus;
+use serde::{
+    Deserialize,
+};
+use tokio::{
+    runtime::{
+        current_thread::{
+            Runtime,
+        },
+    },
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        MissingItemCsv,
+    },
+    ids::{
+        TrackId,
+    },
+    io::{
+        structs_from_file,
+        write_csv_through_receiver,
+    },
+    telemetry::{
+        Telemetry,
+    },
+    track::{
+        get_track_analysis_summary,
+        get_tracks_features,
+    },
+    track_types::{
+        AnalysisAggregates,
+        AnalysisCsv,
+        AudioFeatures,
+        FeaturesCsv,
+    },
+    utils::{
+        ErrorKind,
+        ItemLookup,
+        loop_until_ok,
+    },
+};
+
+// the most IDs get_tracks_features takes at once
+const FEATURES_CHUNK_SIZE: usize = 100;
+// analyses requested together by each thread
+const ANALYSES_IN_FLIGHT: usize = 20;
+
+// tracks_crawled.csv is written by either track crawl, with the ID in id or
+// track_id.
+#[derive(Debug, Deserialize)]
+struct CrawledTrack {
+    #[serde(alias = "id")]
+    track_id: TrackId,
+}
+
+fn crawl_tracks_features_thread(
+    track_ids: Receiver<Vec<TrackId>>,
+    aggregates: AnalysisAggregates,
+    client_ring: Arc<RwLock<ClientRing>>,
+    features_sender: Sender<FeaturesCsv>,
+    analysis_sender: Option<Sender<AnalysisCsv>>,
+    missing_sender: Sender<MissingItemCsv>,
+    progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut rt = Runtime::new().expect("No tokio runtime");
+
+        while let Some(track_ids_chunk) = track_ids.recv().ok() {
+            let tracks_features: Vec<AudioFeatures> = rt.block_on(loop_until_ok(
+                &get_tracks_features,
+                client_ring.clone(),
+                track_ids_chunk.clone(),
+            )).unwrap_or_else(|err| {
+                error!("Unexpected error in track::get_tracks_features: {}", err);
+                ItemLookup::request_failed(track_ids_chunk.len(), &err)
+            }).into_iter().zip(track_ids_chunk.iter()).filter_map(|(features_lookup, track_id)| {
+                features_lookup.missing_report(track_id, &None).map(|missing_item| {
+                    missing_sender.send(missing_item).unwrap_or_else(|err| {
+                        error!(
+                            "Error sending {} missing features through feature_crawl::crawl_tracks_features_thread sender: {}",
+                            track_id,
+                            err,
+                        );
+                    });
+                });
+                match features_lookup {
+                    ItemLookup::Found(audio_features) => Some(audio_features),
+                    ItemLookup::NotFound => {
+                        warn!("No audio features for track {}", track_id);
+                        None
+                    },
+                    ItemLookup::Malformed(err) => {
+                        warn!("Skipping malformed audio features of track {}: {}", track_id, err);
+                        None
+                    },
+                    ItemLookup::RequestFailed(_) => None,
+                }
+            }).collect();
+
+            // Each analysis is reduced as its body streams in, so only
+            // summaries are held while the others are in flight. Some tracks
+            // have features but no analysis, which is a 404 and not retried.
+            let mut tracks_features = tracks_features.into_iter().peekable();
+            while tracks_features.peek().is_some() {
+                let in_flight: Vec<AudioFeatures> = tracks_features.by_ref().take(ANALYSES_IN_FLIGHT).collect();
+                let summaries = rt.block_on(future::join_all(in_flight.iter().map(|audio_features| {
+                    loop_until_ok(
+                        &get_track_analysis_summary,
+                        client_ring.clone(),
+                        (audio_features.id.clone(), aggregates),
+                    ).then(|summary| future::ok::<_, ()>(summary))
+                }))).unwrap_or_default();
+
+                in_flight.into_iter().zip(summaries.into_iter()).map(|(audio_features, summary)| {
+                    let track_id = audio_features.id.clone();
+                    summary.map(|summary| {
+                        analysis_sender.as_ref().map(|analysis_sender| {
+                            analysis_sender.send(AnalysisCsv::extract_from(&summary, track_id.clone()))
+                                .unwrap_or_else(|err| {
+                                    error!(
+                                        "Error sending {} analysis through feature_crawl::crawl_tracks_features_thread sender: {}",
+                                        track_id,
+                                        err,
+                                    );
+                                });
+                        });
+                        features_sender.send(FeaturesCsv::extract_from(&summary, audio_features))
+                            .unwrap_or_else(|err| {
+                                error!(
+                                    "Error sending {} features through feature_crawl::crawl_tracks_features_thread sender: {}",
+                                    track_id,
+                                    err,
+                                );
+                            });
+                    }).unwrap_or_else(|err| {
+                        let status = match err.kind {
+                            ErrorKind::NotFound => {
+                                warn!("Skipping track {} without analysis", track_id);
+                                "analysis_not_found"
+                            },
+                            _ => {
+                                error!("Skipping track {} with undecodable analysis: {}", track_id, err);
+                                "analysis_malformed"
+                            },
+                        };
+                        missing_sender.send(MissingItemCsv {
+                            id: track_id.to_string(),
+                            market: None,
+                            status: status.to_string(),
+                            error: err.message,
+                        }).unwrap_or_else(|err| {
+                            error!(
+                                "Error sending {} missing analysis through feature_crawl::crawl_tracks_features_thread sender: {}",
+                                track_id,
+                                err,
+                            );
+                        });
+                    });
+                }).last();
+            }
+
+            progress.inc(track_ids_chunk.len() as u64);
+            telemetry.record_processed("feature_crawl", track_ids_chunk.len() as u64);
+        }
+    })
+}
+
+pub fn feature_crawl(
+    track_ids: Receiver<Vec<TrackId>>,
+    num_tracks: usize,
+    aggregates: AnalysisAggregates,
+    client_ring: Arc<RwLock<ClientRing>>,
+    features_sender: Sender<FeaturesCsv>,
+    analysis_sender: Option<Sender<AnalysisCsv>>,
+    missing_sender: Sender<MissingItemCsv>,
+) -> thread::Result<()> {
+    let progress = Arc::new(ProgressBar::new(num_tracks as u64));
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
+    );
+
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let num_threads = num_cpus::get();
+    info!("Using {} threads", num_threads);
+
+    let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
+        crawl_tracks_features_thread(
+            track_ids.clone(),
+            aggregates,
+            client_ring.clone(),
+            features_sender.clone(),
+            analysis_sender.clone(),
+            missing_sender.clone(),
+            progress.clone(),
+            telemetry.clone(),
+        )
+    }).collect();
+
+    threads.into_iter().map(|join_handle| {
+        join_handle.join()
+    }).collect::<thread::Result<()>>().and_then(|res| {
+        progress.finish_with_message("Done crawling features");
+        Ok(res)
+    })
+}
+
+// Features and analysis counts of every track in tracks_crawled.csv, plus
+// analyses_crawled.csv when any aggregates are enabled. Tracks without
+// features go to features_missing.csv.
+#[allow(dead_code)]
+pub fn feature_crawl_main(
+    aggregates: AnalysisAggregates,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let (track_id_sender, track_id_receiver) = channel::unbounded();
+    let (features_sender, features_receiver) = channel::unbounded();
+    let (analysis_sender, analysis_receiver) = channel::unbounded();
+    let (missing_sender, missing_receiver) = channel::unbounded();
+
+    // tracks repeat across albums, artists and markets
+    let mut seen_tracks = HashSet::new();
+    let track_ids: Vec<TrackId> = structs_from_file::<CrawledTrack>("tracks_crawled.csv")
+        .expect("Error in reading tracks crawled").into_iter().map(|crawled_track| {
+            crawled_track.track_id
+        }).filter(|track_id| seen_tracks.insert(track_id.clone())).collect();
+    let num_tracks = track_ids.len();
+    track_ids.chunks(FEATURES_CHUNK_SIZE).map(|track_ids_chunk| {
+        track_id_sender.send(track_ids_chunk.to_vec()).expect("Error in sending crawled tracks");
+    }).last();
+    drop(track_id_sender);
+
+    telemetry.register_queue("feature_crawl_input", track_id_receiver.clone());
+    telemetry.register_queue("feature_crawl_output", features_receiver.clone());
+
+    let analysis_sender = if aggregates.pitches || aggregates.timbre {
+        Some(analysis_sender)
+    } else {
+        None
+    };
+
+    let crawler_thread = thread::spawn(move || {
+        feature_crawl(
+            track_id_receiver,
+            num_tracks,
+            aggregates,
+            client_ring,
+            features_sender,
+            analysis_sender,
+            missing_sender,
+        ).expect("Error in crawling features");
+    });
+
+    let features_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(features_receiver, "features_crawled.csv")
+            .expect("Error in writing features");
+    });
+
+    let analysis_writer_thread = thread::spawn(move || {
+        if aggregates.pitches || aggregates.timbre {
+            write_csv_through_receiver(analysis_receiver, "analyses_crawled.csv")
+                .expect("Error in writing analyses");
+        }
+    });
+
+    let missing_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(missing_receiver, "features_missing.csv")
+            .expect("Error in writing missing features");
+    });
+
+    crawler_thread.join().unwrap_or_else(|err| {
+        error!("Error in feature crawler thread: {:?}", err);
+    });
+
+    features_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in features writer thread: {:?}", err);
+    });
+
+    analysis_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in analysis writer thread: {:?}", err);
+    });
+
+    missing_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in missing features writer thread: {:?}", err);
+    });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("feature_crawl").unwrap_or_else(|err| {
+        error!("Error in writing feature_crawl client stats: {}", err);
+    });
+}
@@ -0,0 +1,155 @@
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    str::{
+        FromStr,
+    },
+};
+
+use serde::{
+    de::{
+        self,
+        Deserializer,
+    },
+    ser::{
+        Serializer,
+    },
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    utils::{
+        ErrorKind,
+        SimpleError,
+    },
+};
+
+fn is_base62_id(
+    id: &str,
+) -> bool {
+    id.len() == 22 && id.chars().all(|c| c.is_ascii_alphanumeric())
+}
+
+// Accepts bare IDs, spotify:<kind>:<id> URIs and open.spotify.com/<kind>/<id>
+// links, with or without query strings and intl- path prefixes.
+fn parse_id(
+    input: &str,
+    kind: &str,
+) -> Result<String, SimpleError> {
+    let input = input.trim();
+    let uri_prefix = format!("spotify:{}:", kind);
+
+    let id = if input.starts_with(&uri_prefix[..]) {
+        &input[uri_prefix.len()..]
+    } else if let Some(start) = input.find("open.spotify.com/") {
+        let mut segments = input[start..].split(|c| c == '?' || c == '#').next()
+            .unwrap_or("").split('/').skip(1)
+            .skip_while(|segment| segment.starts_with("intl-"));
+        match (segments.next(), segments.next()) {
+            (Some(segment_kind), Some(id)) if segment_kind == kind => id,
+            _ => return Err(SimpleError {
+                message: format!("{} is not a Spotify {} link", input, kind),
+                kind: ErrorKind::Permanent,
+            }),
+        }
+    } else {
+        input
+    };
+
+    if is_base62_id(id) {
+        Ok(id.to_string())
+    } else {
+        Err(SimpleError {
+            message: format!("{} is not a valid Spotify {} ID", input, kind),
+            kind: ErrorKind::Permanent,
+        })
+    }
+}
+
+macro_rules! spotify_id {
+    ($name:ident, $kind:expr) => {
+        #[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+        pub struct $name(String);
+
+        impl $name {
+            pub fn parse(
+                input: &str,
+            ) -> Result<Self, SimpleError> {
+                parse_id(input, $kind).map($name)
+            }
+
+            pub fn as_str(
+                &self,
+            ) -> &str {
+                &self.0[..]
+            }
+
+            #[allow(dead_code)]
+            pub fn uri(
+                &self,
+            ) -> String {
+                format!("spotify:{}:{}", $kind, self.0)
+            }
+
+            #[allow(dead_code)]
+            pub fn url(
+                &self,
+            ) -> String {
+                format!("https://open.spotify.com/{}/{}", $kind, self.0)
+            }
+        }
+
+        impl Display for $name {
+            fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+                write!(formatter, "{}", self.0)
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = SimpleError;
+
+            fn from_str(
+                input: &str,
+            ) -> Result<Self, Self::Err> {
+                Self::parse(input)
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(
+                &self,
+                serializer: S,
+            ) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(&self.0[..])
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(
+                deserializer: D,
+            ) -> Result<Self, D::Error> {
+                let input = String::deserialize(deserializer)?;
+                Self::parse(&input[..]).map_err(de::Error::custom)
+            }
+        }
+    };
+}
+
+spotify_id!(AlbumId, "album");
+spotify_id!(ArtistId, "artist");
+spotify_id!(EpisodeId, "episode");
+spotify_id!(PlaylistId, "playlist");
+spotify_id!(ShowId, "show");
+spotify_id!(TrackId, "track");
+
+// Comma-separated, as taken by the ?ids= parameter of the multiple-item
+// endpoints.
+pub fn join_ids<I: Display>(
+    ids: &[I],
+) -> String {
+    ids.iter().map(|id| id.to_string()).collect::<Vec<String>>().join(",")
+}
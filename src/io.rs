@@ -1,6 +1,11 @@
 use std::{
+    error::{
+        Error,
+    },
     fs::{
+        self,
         File,
+        OpenOptions,
     },
     io::{
         BufRead,
@@ -83,3 +88,41 @@ pub fn write_csv_through_receiver<S: Serialize>(
 
     Ok(())
 }
+
+pub fn write_csv<S: Serialize>(
+    records: Vec<S>,
+    file_name: &str,
+) -> csv::Result<()> {
+    let mut writer = Writer::from_path(file_name)?;
+    records.into_iter().map(|record| {
+        writer.serialize(record)
+    }).collect::<csv::Result<()>>()?;
+    writer.flush()?;
+
+    Ok(())
+}
+
+pub fn write_private_csv<S: Serialize>(
+    records: Vec<S>,
+    file_name: &str,
+) -> Result<(), Box<dyn Error>> {
+    let tmp_file_name = format!("{}.tmp", file_name);
+
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut writer = Writer::from_writer(options.open(&tmp_file_name[..])?);
+    records.into_iter().map(|record| {
+        writer.serialize(record)
+    }).collect::<csv::Result<()>>()?;
+    writer.flush()?;
+
+    fs::rename(&tmp_file_name[..], file_name)?;
+
+    Ok(())
+}
@@ -1,6 +1,7 @@
 #![feature(impl_trait_in_bindings)]
 
 extern crate atomicring;
+extern crate base64;
 extern crate chashmap;
 extern crate crossbeam_channel;
 extern crate crossbeam_queue;
@@ -12,33 +13,71 @@ extern crate itertools;
 extern crate num;
 extern crate num_cpus;
 extern crate pretty_env_logger;
+extern crate rand;
 extern crate reqwest;
 extern crate serde;
+extern crate serde_ignored;
 extern crate serde_json;
+extern crate serde_path_to_error;
+extern crate sha2;
 extern crate tokio;
+extern crate unicode_normalization;
 
 mod album;
 mod album_crawl;
 mod album_types;
+mod api_enums;
 mod artist;
 mod artist_crawl;
 mod artist_types;
+mod browse;
+mod browse_types;
 mod client;
 mod common_types;
+mod credentials;
+mod decode;
 mod feature_crawl;
+mod ids;
 mod io;
+mod market;
+mod metrics;
+mod playlist;
+mod playlist_crawl;
+mod playlist_types;
+mod recommendation_crawl;
+mod recommendation_types;
+mod recommendations;
+mod release_date;
+mod schema_drift;
+mod search;
+mod seed;
+mod show;
+mod show_crawl;
+mod show_types;
+mod telemetry;
 mod test;
+mod token_cache;
 mod track;
 mod track_crawl;
 mod track_crawl_2;
 mod track_types;
+mod user;
+mod user_auth;
+mod user_types;
 mod utils;
 
 use std::{
+    env,
+    path::{
+        PathBuf,
+    },
     sync::{
         Arc,
         RwLock,
     },
+    time::{
+        Duration,
+    },
 };
 
 use reqwest::{
@@ -51,14 +90,112 @@ fn main(
 ) {
     pretty_env_logger::init();
 
-    // options for proxies
+    // optional decode benchmark over a fixture instead of a crawl, e.g. BENCH_DECODE=1000
+    if let Ok(iterations) = env::var("BENCH_DECODE") {
+        test::bench_decode(iterations.parse().expect("Invalid BENCH_DECODE iterations"));
+        return;
+    }
+
+    // options for credentials (ClientsFile, SecretsFile, Environment, Stdin) and proxies
     let client_ring = Arc::new(RwLock::new(
-        client::ClientRing::init(Client::new(), false).expect("Error in initializing client ring")
+        client::ClientRing::init(
+            Client::new(),
+            credentials::CredentialSource::ClientsFile("clients.csv".to_string()),
+            token_cache::TokenCache::load("token_cache.csv"),
+            false,
+        ).expect("Error in initializing client ring")
     ));
+    telemetry::Telemetry::spawn_summary_logger(
+        client_ring.read().expect("client ring RwLock poisoned").telemetry(),
+        Duration::from_secs(300),
+    );
+
+    // optional Prometheus endpoint, e.g. METRICS_ADDR=127.0.0.1:9898
+    if let Ok(address) = env::var("METRICS_ADDR") {
+        metrics::serve_metrics(
+            client_ring.read().expect("client ring RwLock poisoned").telemetry(),
+            &address[..],
+        ).expect("Error in starting metrics endpoint");
+    }
+
+    // optional directory for response bodies that fail to decode, e.g. DIAGNOSTICS_DIR=diagnostics
+    if let Ok(diagnostics_dir) = env::var("DIAGNOSTICS_DIR") {
+        client_ring.write().expect("client ring RwLock poisoned")
+            .set_diagnostics_dir(Some(PathBuf::from(diagnostics_dir)));
+    }
+
+    // optional tally of fields our types do not match, written to schema_drift.csv
+    // with every stage dump, e.g. SCHEMA_DRIFT=1
+    if env::var("SCHEMA_DRIFT").is_ok() {
+        client_ring.read().expect("client ring RwLock poisoned").telemetry().enable_schema_drift();
+    }
+
+    // user-scoped client for me/ endpoints, authorized in the browser on first run
+    // let user_client_ring = Arc::new(RwLock::new(
+    //     client::ClientRing::init_user(
+    //         Client::new(),
+    //         credentials::CredentialSource::ClientsFile("clients.csv".to_string()).load()
+    //             .expect("Error in loading credentials").remove(0),
+    //         vec!["user-library-read".to_string(), "user-follow-read".to_string()],
+    //         8888,
+    //         "user_token.csv",
+    //     ).expect("Error in initializing user client ring")
+    // ));
 
     artist_crawl::artist_crawl_main(25, client_ring.clone());
 
-    // track_crawl_2::track_crawl_main(client_ring.clone());
+    // seeds from what is being surfaced in a market instead of seed_artists.txt
+    // artist_crawl::artist_crawl_browse_main(
+    //     25,
+    //     browse::BrowseOptions {
+    //         country: Some("US".to_string()),
+    //         ..Default::default()
+    //     },
+    //     5,
+    //     100,
+    //     client_ring.clone(),
+    // );
+
+    // seeds from genre:"..." searches over the genres in seed_genres.txt
+    // artist_crawl::artist_crawl_genres_main(25, 10, client_ring.clone());
+
+    // markets to crawl the catalogue under, e.g. to compare relinking and availability
+    // let markets: Vec<market::Market> = vec!["US", "GB", "JP"].into_iter().map(|code| {
+    //     code.parse().expect("Invalid market")
+    // }).collect();
+
+    // album_crawl::album_crawl_main(
+    //     artist::ArtistAlbumsOptions {
+    //         include_groups: vec![
+    //             api_enums::AlbumGroup::Album,
+    //             api_enums::AlbumGroup::Single,
+    //             api_enums::AlbumGroup::AppearsOn,
+    //         ],
+    //         limit: Some(50),
+    //         market: Some(markets[0].clone()),
+    //     },
+    //     client_ring.clone(),
+    // );
+
+    // track_crawl_2::track_crawl_main(markets.clone(), client_ring.clone());
+
+    // track_crawl_2::top_tracks_by_market_main(markets.clone(), client_ring.clone());
+
+    // playlist_crawl::playlist_crawl_main(None, client_ring.clone());
+
+    // playlist_crawl::playlist_owner_crawl_main(10000, None, client_ring.clone());
+
+    // shows from seed_shows.txt plus those behind episodes in crawled playlists
+    // show_crawl::show_crawl_main(markets[0].clone(), true, client_ring.clone());
+
+    // recommendation_crawl::recommendation_crawl_main(0.1, 20, client_ring.clone());
 
-    // feature_crawl::feature_crawl_main(client_ring);
+    // feature_crawl::feature_crawl_main(
+    //     track_types::AnalysisAggregates {
+    //         pitches: true,
+    //         timbre: true,
+    //         adjust_pitches: true,
+    //     },
+    //     client_ring,
+    // );
 }
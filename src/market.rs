@@ -0,0 +1,115 @@
+use std::{
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    str::{
+        FromStr,
+    },
+};
+
+use serde::{
+    de::{
+        self,
+        Deserializer,
+    },
+    ser::{
+        Serializer,
+    },
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    utils::{
+        ErrorKind,
+        SimpleError,
+    },
+};
+
+// Market passed to endpoints that relink tracks. With a market, unavailable
+// tracks are swapped for a playable copy and carry linked_from and
+// is_playable.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Market {
+    // ISO 3166-1 alpha-2 code
+    Country(String),
+    // the country of the token's user, only valid on user client rings
+    FromToken,
+}
+
+impl Market {
+    pub fn parse(
+        input: &str,
+    ) -> Result<Self, SimpleError> {
+        let input = input.trim();
+        if input == "from_token" {
+            return Ok(Market::FromToken);
+        }
+        if input.len() == 2 && input.chars().all(|c| c.is_ascii_alphabetic()) {
+            return Ok(Market::Country(input.to_ascii_uppercase()));
+        }
+
+        Err(SimpleError {
+            message: format!("{} is not a country code or from_token", input),
+            kind: ErrorKind::Permanent,
+        })
+    }
+
+    pub fn code(
+        &self,
+    ) -> &str {
+        match self {
+            Market::Country(country) => &country[..],
+            Market::FromToken => "from_token",
+        }
+    }
+}
+
+impl Display for Market {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.code())
+    }
+}
+
+impl FromStr for Market {
+    type Err = SimpleError;
+
+    fn from_str(
+        input: &str,
+    ) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+impl Serialize for Market {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.code())
+    }
+}
+
+impl<'de> Deserialize<'de> for Market {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Self::parse(&input[..]).map_err(de::Error::custom)
+    }
+}
+
+pub fn with_market(
+    url: String,
+    market: &Option<Market>,
+) -> String {
+    match market {
+        Some(market) => {
+            let separator = if url.contains('?') { '&' } else { '?' };
+            format!("{}{}market={}", url, separator, market.code())
+        },
+        None => url,
+    }
+}
@@ -0,0 +1,181 @@
+use std::{
+    fmt::{
+        Write as FmtWrite,
+    },
+    io::{
+        self,
+        BufRead,
+        BufReader,
+        Write,
+    },
+    net::{
+        TcpListener,
+        TcpStream,
+    },
+    sync::{
+        Arc,
+    },
+    thread,
+};
+
+use crate::{
+    telemetry::{
+        Telemetry,
+    },
+};
+
+// Serves the telemetry of a client ring in the Prometheus text format on
+// http://<address>/metrics until the process exits.
+pub fn serve_metrics(
+    telemetry: Arc<Telemetry>,
+    address: &str,
+) -> io::Result<thread::JoinHandle<()>> {
+    let listener = TcpListener::bind(address)?;
+    info!("Serving metrics on http://{}/metrics", address);
+
+    Ok(thread::spawn(move || {
+        listener.incoming().map(|stream| {
+            stream.and_then(|stream| {
+                respond(stream, &telemetry)
+            }).unwrap_or_else(|err| {
+                warn!("Error in serving metrics request: {}", err);
+            });
+        }).last();
+    }))
+}
+
+fn respond(
+    mut stream: TcpStream,
+    telemetry: &Telemetry,
+) -> io::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream).read_line(&mut request_line)?;
+    let target = request_line.split_whitespace().nth(1).unwrap_or("");
+
+    if target != "/metrics" {
+        return stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n");
+    }
+
+    let body = render(telemetry);
+    write!(
+        stream,
+        "HTTP/1.1 200 OK\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        body.len(),
+        body,
+    )
+}
+
+fn escape_label(
+    value: &str,
+) -> String {
+    value.replace("\\", "\\\\").replace("\"", "\\\"").replace("\n", "\\n")
+}
+
+fn header(
+    body: &mut String,
+    name: &str,
+    metric_type: &str,
+    help: &str,
+) {
+    writeln!(body, "# HELP {} {}", name, help).unwrap_or(());
+    writeln!(body, "# TYPE {} {}", name, metric_type).unwrap_or(());
+}
+
+pub fn render(
+    telemetry: &Telemetry,
+) -> String {
+    let mut body = String::new();
+
+    header(&mut body, "crawler_items_processed_total", "counter", "Items processed per crawl stage.");
+    telemetry.items_processed().into_iter().map(|(stage, count)| {
+        writeln!(body, "crawler_items_processed_total{{stage=\"{}\"}} {}", escape_label(&stage[..]), count)
+            .unwrap_or(());
+    }).last();
+
+    header(&mut body, "crawler_queue_depth", "gauge", "Messages waiting in crawl stage channels.");
+    telemetry.queue_depths().into_iter().map(|(queue, depth)| {
+        writeln!(body, "crawler_queue_depth{{queue=\"{}\"}} {}", escape_label(&queue[..]), depth)
+            .unwrap_or(());
+    }).last();
+
+    let (ring_clients, ring_cooling_down) = telemetry.ring_state();
+    header(&mut body, "client_ring_clients", "gauge", "Clients in the client ring.");
+    writeln!(body, "client_ring_clients {}", ring_clients).unwrap_or(());
+    header(&mut body, "client_ring_cooling_down", "gauge", "Clients sleeping off a 429.");
+    writeln!(body, "client_ring_cooling_down {}", ring_cooling_down).unwrap_or(());
+
+    let run_stats = telemetry.run_stats();
+
+    header(&mut body, "spotify_requests_total", "counter", "Requests sent per client.");
+    run_stats.iter().map(|stats| {
+        writeln!(body, "spotify_requests_total{{client=\"{}\"}} {}", escape_label(&stats.client[..]), stats.requests)
+            .unwrap_or(());
+    }).last();
+
+    header(&mut body, "spotify_responses_total", "counter", "Responses per client by class.");
+    run_stats.iter().map(|stats| {
+        vec![
+            ("ok", stats.successes),
+            ("too_many_requests", stats.too_many_requests),
+            ("unauthorized", stats.unauthorized),
+            ("other_status", stats.other_statuses),
+            ("transport_error", stats.transport_errors),
+        ].into_iter().map(|(class, count)| {
+            writeln!(
+                body,
+                "spotify_responses_total{{client=\"{}\",class=\"{}\"}} {}",
+                escape_label(&stats.client[..]),
+                class,
+                count,
+            ).unwrap_or(());
+        }).last();
+    }).last();
+
+    header(&mut body, "spotify_cooldown_seconds_total", "counter", "Seconds clients were put to sleep after 429s.");
+    run_stats.iter().map(|stats| {
+        writeln!(
+            body,
+            "spotify_cooldown_seconds_total{{client=\"{}\"}} {}",
+            escape_label(&stats.client[..]),
+            stats.cooldown_secs,
+        ).unwrap_or(());
+    }).last();
+
+    header(&mut body, "spotify_request_duration_seconds", "histogram", "Request latency per client.");
+    run_stats.iter().map(|stats| {
+        let client = escape_label(&stats.client[..]);
+        let mut cumulative = 0;
+        vec![
+            ("0.1", stats.latency_le_100ms),
+            ("0.25", stats.latency_le_250ms),
+            ("0.5", stats.latency_le_500ms),
+            ("1", stats.latency_le_1000ms),
+            ("2.5", stats.latency_le_2500ms),
+            ("5", stats.latency_le_5000ms),
+            ("+Inf", stats.latency_over_5000ms),
+        ].into_iter().map(|(le, count)| {
+            cumulative += count;
+            writeln!(
+                body,
+                "spotify_request_duration_seconds_bucket{{client=\"{}\",le=\"{}\"}} {}",
+                client,
+                le,
+                cumulative,
+            ).unwrap_or(());
+        }).last();
+        writeln!(
+            body,
+            "spotify_request_duration_seconds_sum{{client=\"{}\"}} {}",
+            client,
+            stats.latency_total_ms as f64 / 1000f64,
+        ).unwrap_or(());
+        writeln!(
+            body,
+            "spotify_request_duration_seconds_count{{client=\"{}\"}} {}",
+            client,
+            stats.requests,
+        ).unwrap_or(());
+    }).last();
+
+    body
+}
@@ -0,0 +1,85 @@
+use std::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use futures::{
+    Future,
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        Paging,
+    },
+    ids::{
+        PlaylistId,
+    },
+    market::{
+        Market,
+        with_market,
+    },
+    playlist_types::{
+        PlaylistFull,
+        PlaylistSimple,
+        PlaylistTrack,
+    },
+    user::{
+        user_url,
+    },
+    utils::{
+        get_with_retry,
+        SimpleError,
+    },
+};
+
+type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
+
+pub fn get_playlist(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (playlist_id, market): (PlaylistId, Option<Market>),
+) -> CustomFuture<PlaylistFull> {
+    Box::new(
+        get_with_retry(
+            with_market(
+                format!("https://api.spotify.com/v1/playlists/{}/?additional_types=track,episode", playlist_id),
+                &market,
+            ),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_playlist_tracks(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (playlist_id, market): (PlaylistId, Option<Market>),
+) -> CustomFuture<Paging<PlaylistTrack>> {
+    Box::new(
+        get_with_retry(
+            with_market(format!(
+                "https://api.spotify.com/v1/playlists/{}/tracks/?limit=100&additional_types=track,episode",
+                playlist_id,
+            ), &market),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_user_playlists(
+    client_ring: Arc<RwLock<ClientRing>>,
+    user_id: String,
+) -> CustomFuture<Paging<PlaylistSimple>> {
+    let mut url = user_url(&user_id[..], &["playlists"]);
+    url.set_query(Some("limit=50"));
+
+    Box::new(
+        get_with_retry(
+            url.into_string(),
+            client_ring,
+        )
+    )
+}
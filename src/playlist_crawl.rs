@@ -0,0 +1,430 @@
+use std::{
+    collections::{
+        HashSet,
+    },
+    error::{
+        Error,
+    },
+    sync::{
+        Arc,
+        RwLock,
+    },
+    thread,
+};
+
+use crossbeam_channel::{
+    self as channel,
+    Receiver,
+    Sender,
+};
+use futures::{
+    Future,
+};
+use indicatif::{
+    ProgressBar,
+    ProgressStyle,
+};
+use num_cpus;
+use tokio::{
+    runtime::{
+        current_thread::{
+            Runtime,
+        },
+    },
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    ids::{
+        PlaylistId,
+    },
+    io::{
+        lines_from_file,
+        write_csv_through_receiver,
+    },
+    market::{
+        Market,
+    },
+    playlist::{
+        get_playlist,
+        get_user_playlists,
+    },
+    playlist_types::{
+        PlaylistCsv,
+        PlaylistSimple,
+        PlaylistTrack,
+        PlaylistTrackCsv,
+    },
+    telemetry::{
+        Telemetry,
+    },
+    user::{
+        get_user,
+    },
+    user_types::{
+        UserCsv,
+    },
+    utils::{
+        ErrorKind,
+        get_next_paging,
+        loop_until_ok,
+        SimpleError,
+    },
+};
+
+fn crawl_playlists_thread(
+    playlist_ids: Receiver<PlaylistId>,
+    market: Option<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    playlist_sender: Sender<PlaylistCsv>,
+    playlist_track_sender: Sender<PlaylistTrackCsv>,
+    progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut rt = Runtime::new().expect("No tokio runtime");
+
+        while let Some(playlist_id) = playlist_ids.recv().ok() {
+            let (mut items, mut next) = rt.block_on(loop_until_ok(
+                &get_playlist,
+                client_ring.clone(),
+                (playlist_id.clone(), market.clone()),
+            )).map(|playlist_full| {
+                playlist_sender.send(PlaylistCsv::extract_from(&playlist_full)).unwrap_or_else(|err| {
+                    error!(
+                        "Error sending {} through playlist_crawl::crawl_playlists_thread sender: {}",
+                        playlist_id,
+                        err,
+                    );
+                });
+                (Some(playlist_full.tracks.items), playlist_full.tracks.next)
+            }).unwrap_or_else(|err| {
+                error!(
+                    "Unexpected error in playlist::get_playlist for {}: {}",
+                    playlist_id,
+                    err,
+                );
+                (None, None)
+            });
+
+            let mut position = 0;
+            while let Some(paging_items) = items {
+                paging_items.into_iter().map(|playlist_track: PlaylistTrack| {
+                    position += 1;
+                    playlist_track_sender.send(PlaylistTrackCsv::extract_from(
+                        playlist_track,
+                        playlist_id.clone(),
+                        position - 1,
+                    )).map_err(|err| SimpleError {
+                        message: err.to_string(),
+                        kind: ErrorKind::Permanent,
+                    }.into())
+                }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
+                    error!(
+                        "Error sending {} data through playlist_crawl::crawl_playlists_thread sender: {}",
+                        playlist_id,
+                        err,
+                    );
+                });
+
+                let (items_new, next_new) = next.map(|next_paging_url| {
+                    rt.block_on(loop_until_ok(
+                        &get_next_paging,
+                        client_ring.clone(),
+                        next_paging_url.clone(),
+                    ).map(|paging| {
+                        (Some(paging.items), paging.next)
+                    })).unwrap_or_else(|err| {
+                        error!(
+                            "Unexpected error in getting next paging with URL {}: {}",
+                            next_paging_url,
+                            err,
+                        );
+                        (None, None)
+                    })
+                }).unwrap_or((None, None));
+
+                items = items_new;
+                next = next_new;
+            }
+
+            progress.inc(1);
+            telemetry.record_processed("playlist_crawl", 1);
+        }
+    })
+}
+
+pub fn playlist_crawl(
+    playlist_ids: Receiver<PlaylistId>,
+    num_playlists: usize,
+    market: Option<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    playlist_sender: Sender<PlaylistCsv>,
+    playlist_track_sender: Sender<PlaylistTrackCsv>,
+) -> thread::Result<()> {
+    let progress = Arc::new(ProgressBar::new(num_playlists as u64));
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
+    );
+
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let num_threads = num_cpus::get();
+    info!("Using {} threads", num_threads);
+
+    let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
+        crawl_playlists_thread(
+            playlist_ids.clone(),
+            market.clone(),
+            client_ring.clone(),
+            playlist_sender.clone(),
+            playlist_track_sender.clone(),
+            progress.clone(),
+            telemetry.clone(),
+        )
+    }).collect();
+
+    threads.into_iter().map(|join_handle| {
+        join_handle.join()
+    }).collect::<thread::Result<()>>().and_then(|res| {
+        progress.finish_with_message("Done crawling playlists");
+        Ok(res)
+    })
+}
+
+#[allow(dead_code)]
+pub fn playlist_crawl_main(
+    market: Option<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let (playlist_id_sender, playlist_id_receiver) = channel::unbounded();
+    let (playlist_sender, playlist_receiver) = channel::unbounded();
+    let (playlist_track_sender, playlist_track_receiver) = channel::unbounded();
+
+    // seed playlists may be given as IDs, URIs or open.spotify.com links
+    let playlist_ids: Vec<PlaylistId> = lines_from_file("seed_playlists.txt")
+        .expect("Error in reading seed playlists").into_iter().filter_map(|line| {
+            PlaylistId::parse(&line[..]).map_err(|err| {
+                warn!("Skipping seed playlist: {}", err);
+            }).ok()
+        }).collect();
+    let num_playlists = playlist_ids.len();
+    playlist_ids.into_iter().map(|playlist_id| {
+        playlist_id_sender.send(playlist_id).expect("Error in sending seed playlist");
+    }).last();
+    drop(playlist_id_sender);
+
+    telemetry.register_queue("playlist_crawl_input", playlist_id_receiver.clone());
+    telemetry.register_queue("playlist_crawl_output", playlist_track_receiver.clone());
+
+    let crawler_thread = thread::spawn(move || {
+        playlist_crawl(
+            playlist_id_receiver,
+            num_playlists,
+            market,
+            client_ring,
+            playlist_sender,
+            playlist_track_sender,
+        ).expect("Error in crawling playlists");
+    });
+
+    let playlist_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(playlist_receiver, "playlists_crawled.csv")
+            .expect("Error in writing playlists");
+    });
+
+    let playlist_track_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(playlist_track_receiver, "playlist_tracks_crawled.csv")
+            .expect("Error in writing playlist tracks");
+    });
+
+    crawler_thread.join().unwrap_or_else(|err| {
+        error!("Error in playlist crawler thread: {:?}", err);
+    });
+
+    playlist_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in playlist writer thread: {:?}", err);
+    });
+
+    playlist_track_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in playlist track writer thread: {:?}", err);
+    });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("playlist_crawl").unwrap_or_else(|err| {
+        error!("Error in writing playlist_crawl client stats: {}", err);
+    });
+}
+
+// Editorial owners whose catalogues would swamp listener-curated playlists.
+const SKIPPED_OWNERS: [&str; 1] = ["spotify"];
+
+// Public playlists the user owns; followed playlists are left out since they
+// are curated by someone else. Not looped until ok: deleted users are 404s.
+fn owned_public_playlists(
+    user_id: &str,
+    client_ring: Arc<RwLock<ClientRing>>,
+    rt: &mut Runtime,
+) -> Vec<PlaylistId> {
+    let (mut items, mut next) = rt.block_on(get_user_playlists(
+        client_ring.clone(),
+        user_id.to_string(),
+    )).map(|paging| {
+        (Some(paging.items), paging.next)
+    }).unwrap_or_else(|err| {
+        warn!("Skipping playlists of user {}: {}", user_id, err);
+        (None, None)
+    });
+
+    let mut playlist_ids = Vec::new();
+    while let Some(paging_items) = items {
+        playlist_ids.extend(paging_items.into_iter().filter(|playlist_simple: &PlaylistSimple| {
+            playlist_simple.owner.id == user_id
+        }).map(|playlist_simple| playlist_simple.id));
+
+        let (items_new, next_new) = next.map(|next_paging_url| {
+            rt.block_on(loop_until_ok(
+                &get_next_paging,
+                client_ring.clone(),
+                next_paging_url.clone(),
+            ).map(|paging| {
+                (Some(paging.items), paging.next)
+            })).unwrap_or_else(|err| {
+                error!(
+                    "Unexpected error in getting next paging with URL {}: {}",
+                    next_paging_url,
+                    err,
+                );
+                (None, None)
+            })
+        }).unwrap_or((None, None));
+
+        items = items_new;
+        next = next_new;
+    }
+
+    playlist_ids
+}
+
+// Crawls seed_playlists.txt, then walks in rounds from the owners of crawled
+// playlists to their other public playlists until max_playlists are crawled.
+#[allow(dead_code)]
+pub fn playlist_owner_crawl_main(
+    max_playlists: usize,
+    market: Option<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let (playlist_sender, playlist_receiver) = channel::unbounded();
+    let (playlist_track_sender, playlist_track_receiver) = channel::unbounded();
+    let (user_sender, user_receiver) = channel::unbounded();
+
+    telemetry.register_queue("playlist_owner_crawl_output", playlist_track_receiver.clone());
+
+    let playlist_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(playlist_receiver, "playlists_crawled.csv")
+            .expect("Error in writing playlists");
+    });
+
+    let playlist_track_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(playlist_track_receiver, "playlist_tracks_crawled.csv")
+            .expect("Error in writing playlist tracks");
+    });
+
+    let user_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(user_receiver, "users_crawled.csv")
+            .expect("Error in writing users");
+    });
+
+    let mut seen_playlists = HashSet::new();
+    let mut seen_users = HashSet::new();
+    let mut num_crawled = 0;
+    let mut frontier: Vec<PlaylistId> = lines_from_file("seed_playlists.txt")
+        .expect("Error in reading seed playlists").into_iter().filter_map(|line| {
+            PlaylistId::parse(&line[..]).map_err(|err| {
+                warn!("Skipping seed playlist: {}", err);
+            }).ok()
+        }).filter(|playlist_id| seen_playlists.insert(playlist_id.clone())).collect();
+
+    let mut round = 0;
+    while !frontier.is_empty() && num_crawled < max_playlists {
+        frontier.truncate(max_playlists - num_crawled);
+        num_crawled += frontier.len();
+        info!("Owner walk round {}: crawling {} playlists", round, frontier.len());
+
+        let (playlist_id_sender, playlist_id_receiver) = channel::unbounded();
+        let (round_playlist_sender, round_playlist_receiver) = channel::unbounded();
+        let num_playlists = frontier.len();
+        frontier.drain(..).map(|playlist_id| {
+            playlist_id_sender.send(playlist_id).expect("Error in sending frontier playlist");
+        }).last();
+        drop(playlist_id_sender);
+
+        playlist_crawl(
+            playlist_id_receiver,
+            num_playlists,
+            market.clone(),
+            client_ring.clone(),
+            round_playlist_sender,
+            playlist_track_sender.clone(),
+        ).expect("Error in crawling playlists");
+
+        let owner_ids: Vec<String> = round_playlist_receiver.try_iter().map(|playlist_csv: PlaylistCsv| {
+            let owner_id = playlist_csv.owner_id.clone();
+            playlist_sender.send(playlist_csv).unwrap_or_else(|err| {
+                error!("Error sending through playlist_crawl::playlist_owner_crawl_main sender: {}", err);
+            });
+            owner_id
+        }).filter(|owner_id| {
+            !SKIPPED_OWNERS.contains(&&owner_id[..]) && seen_users.insert(owner_id.clone())
+        }).collect();
+
+        owner_ids.into_iter().map(|owner_id| {
+            let playlist_ids = owned_public_playlists(&owner_id[..], client_ring.clone(), &mut rt);
+
+            rt.block_on(get_user(
+                client_ring.clone(),
+                owner_id.clone(),
+            )).map(|public_user| {
+                user_sender.send(UserCsv::extract_from(public_user, playlist_ids.len())).unwrap_or_else(|err| {
+                    error!("Error sending {} through playlist_crawl::playlist_owner_crawl_main sender: {}", owner_id, err);
+                });
+            }).unwrap_or_else(|err| {
+                warn!("Skipping profile of user {}: {}", owner_id, err);
+            });
+
+            frontier.extend(playlist_ids.into_iter().filter(|playlist_id| {
+                seen_playlists.insert(playlist_id.clone())
+            }));
+            telemetry.record_processed("playlist_owner_crawl", 1);
+        }).last();
+
+        round += 1;
+    }
+
+    drop(playlist_sender);
+    drop(playlist_track_sender);
+    drop(user_sender);
+
+    playlist_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in playlist writer thread: {:?}", err);
+    });
+
+    playlist_track_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in playlist track writer thread: {:?}", err);
+    });
+
+    user_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in user writer thread: {:?}", err);
+    });
+
+    info!("Crawled {} playlists of {} owners in {} rounds", num_crawled, seen_users.len(), round);
+    telemetry.log_summary();
+    telemetry.dump_stage("playlist_owner_crawl").unwrap_or_else(|err| {
+        error!("Error in writing playlist_owner_crawl client stats: {}", err);
+    });
+}
@@ -0,0 +1,192 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+
+use crate::{
+    artist_types::{
+        Followers,
+    },
+    common_types::{
+        Image,
+        Paging,
+    },
+    ids::{
+        PlaylistId,
+    },
+    show_types::{
+        EpisodeFull,
+    },
+    track_types::{
+        TrackFull,
+    },
+    user_types::{
+        PublicUser,
+    },
+};
+
+macro_rules! with_playlist_core_fields {
+    (pub struct $name:ident { $( pub $field:ident: $ty:ty ),* $(,)* }) => {
+        #[derive(Debug, Deserialize, Serialize)]
+        pub struct $name {
+            pub collaborative: bool,
+            pub description: Option<String>,
+            pub external_urls: Map<String, Value>,
+            pub href: String,
+            pub id: PlaylistId,
+            pub images: Option<Vec<Image>>,
+            pub name: String,
+            pub owner: PublicUser,
+            pub public: Option<bool>,
+            pub snapshot_id: String,
+            pub uri: String,
+            #[serde(rename = "type")]
+            pub object_type: String,
+            $( pub $field: $ty ),*
+        }
+    };
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlaylistTracksRef {
+    pub href: String,
+    pub total: i32,
+}
+
+with_playlist_core_fields!(pub struct PlaylistSimple {
+    pub tracks: PlaylistTracksRef,
+});
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct LocalTrack {
+    pub duration_ms: i32,
+    pub name: Option<String>,
+    pub uri: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+}
+
+// Tried in order: local tracks have a null id, so they only match Local.
+#[derive(Debug, Deserialize, Serialize)]
+#[serde(untagged)]
+pub enum PlaylistItem {
+    Track(TrackFull),
+    Episode(EpisodeFull),
+    Local(LocalTrack),
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlaylistTrack {
+    pub added_at: Option<String>,
+    pub added_by: Option<PublicUser>,
+    pub is_local: bool,
+    pub track: Option<PlaylistItem>,
+}
+
+with_playlist_core_fields!(pub struct PlaylistFull {
+    pub followers: Followers,
+    pub tracks: Paging<PlaylistTrack>,
+});
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlaylistCsv {
+    pub id: PlaylistId,
+    pub name: String,
+    pub owner_id: String,
+    pub owner_name: String,
+    pub description: String,
+    pub collaborative: bool,
+    pub public: Option<bool>,
+    pub followers_total: i32,
+    pub num_tracks: i32,
+    pub snapshot_id: String,
+}
+
+impl PlaylistCsv {
+    pub fn extract_from(
+        playlist_full: &PlaylistFull,
+    ) -> Self {
+        Self {
+            id: playlist_full.id.clone(),
+            name: playlist_full.name.clone(),
+            owner_id: playlist_full.owner.id.clone(),
+            owner_name: playlist_full.owner.display_name.clone().unwrap_or("".to_string()),
+            description: playlist_full.description.clone().unwrap_or("".to_string()),
+            collaborative: playlist_full.collaborative,
+            public: playlist_full.public,
+            followers_total: playlist_full.followers.total,
+            num_tracks: playlist_full.tracks.total,
+            snapshot_id: playlist_full.snapshot_id.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlaylistTrackCsv {
+    pub playlist_id: PlaylistId,
+    pub position: usize,
+    pub item_type: String,
+    pub item_id: String,
+    pub item_name: String,
+    pub item_uri: String,
+    pub artist_ids: String,
+    pub added_at: String,
+    pub added_by: String,
+}
+
+impl PlaylistTrackCsv {
+    pub fn extract_from(
+        playlist_track: PlaylistTrack,
+        playlist_id: PlaylistId,
+        position: usize,
+    ) -> Self {
+        let (item_type, item_id, item_name, item_uri, artist_ids) = match playlist_track.track {
+            Some(PlaylistItem::Track(track_full)) => (
+                "track",
+                track_full.id.to_string(),
+                track_full.name,
+                track_full.uri,
+                track_full.artists.into_iter().map(|artist_simple| {
+                    artist_simple.id.to_string()
+                }).collect::<Vec<String>>().join(", "),
+            ),
+            Some(PlaylistItem::Episode(episode)) => (
+                "episode",
+                episode.id.to_string(),
+                episode.name,
+                episode.uri,
+                "".to_string(),
+            ),
+            Some(PlaylistItem::Local(local_track)) => (
+                "local",
+                "".to_string(),
+                local_track.name.unwrap_or("".to_string()),
+                local_track.uri,
+                "".to_string(),
+            ),
+            None => (
+                "unavailable",
+                "".to_string(),
+                "".to_string(),
+                "".to_string(),
+                "".to_string(),
+            ),
+        };
+
+        Self {
+            playlist_id: playlist_id,
+            position: position,
+            item_type: item_type.to_string(),
+            item_id: item_id,
+            item_name: item_name,
+            item_uri: item_uri,
+            artist_ids: artist_ids,
+            added_at: playlist_track.added_at.unwrap_or("".to_string()),
+            added_by: playlist_track.added_by.map(|user| user.id).unwrap_or("".to_string()),
+        }
+    }
+}
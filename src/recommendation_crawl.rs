@@ -0,0 +1,253 @@
+use std::{
+    error::{
+        Error,
+    },
+    sync::{
+        Arc,
+        RwLock,
+    },
+    thread,
+};
+
+use chashmap::{
+    CHashMap,
+};
+use crossbeam_channel::{
+    self as channel,
+    Receiver,
+    Sender,
+};
+use indicatif::{
+    ProgressBar,
+    ProgressStyle,
+};
+use num_cpus;
+use tokio::{
+    runtime::{
+        current_thread::{
+            Runtime,
+        },
+    },
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    ids::{
+        TrackId,
+    },
+    io::{
+        lines_from_file,
+        read_csv_into_sender,
+        structs_from_file,
+        write_csv_through_receiver,
+    },
+    recommendation_types::{
+        RecommendationCsv,
+    },
+    recommendations::{
+        AttributeRange,
+        get_recommendations,
+        RecommendationsQuery,
+        TunableAttribute,
+    },
+    telemetry::{
+        Telemetry,
+    },
+    track_types::{
+        FeaturesCsv,
+    },
+    utils::{
+        ErrorKind,
+        loop_until_ok,
+        SimpleError,
+    },
+};
+
+fn range_around(
+    value: f32,
+    window: f32,
+) -> AttributeRange {
+    AttributeRange {
+        min: Some((value - window).max(0f32)),
+        max: Some((value + window).min(1f32)),
+        target: Some(value),
+    }
+}
+
+// Recommendations seeded by the track itself and constrained to a window
+// around its danceability, energy and valence, targeting its tempo.
+fn query_around(
+    features_csv: &FeaturesCsv,
+    window: f32,
+    samples_per_track: u32,
+) -> RecommendationsQuery {
+    RecommendationsQuery {
+        seed_tracks: vec![features_csv.track_id.clone()],
+        attributes: vec![
+            (TunableAttribute::Danceability, range_around(features_csv.danceability, window)),
+            (TunableAttribute::Energy, range_around(features_csv.energy, window)),
+            (TunableAttribute::Valence, range_around(features_csv.valence, window)),
+            (TunableAttribute::Tempo, AttributeRange {
+                target: Some(features_csv.tempo),
+                ..Default::default()
+            }),
+        ],
+        limit: Some(samples_per_track),
+        ..Default::default()
+    }
+}
+
+fn crawl_recommendations_thread(
+    features_crawled: Receiver<FeaturesCsv>,
+    window: f32,
+    samples_per_track: u32,
+    known_tracks: Arc<CHashMap<TrackId, ()>>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    sender: Sender<RecommendationCsv>,
+    progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut rt = Runtime::new().expect("No tokio runtime");
+
+        while let Some(features_csv) = features_crawled.recv().ok() {
+            rt.block_on(loop_until_ok(
+                &get_recommendations,
+                client_ring.clone(),
+                query_around(&features_csv, window, samples_per_track),
+            )).map(|recommendations| {
+                recommendations.tracks
+            }).unwrap_or_else(|err| {
+                error!(
+                    "Unexpected error in recommendations::get_recommendations for {}: {}",
+                    features_csv.track_id,
+                    err,
+                );
+                vec![]
+            }).into_iter().filter(|track_simple| {
+                known_tracks.insert(track_simple.id.clone(), ()).is_none()
+            }).map(|track_simple| {
+                sender.send(RecommendationCsv::extract_from(
+                    track_simple,
+                    features_csv.track_id.clone(),
+                )).map_err(|err| SimpleError {
+                    message: err.to_string(),
+                    kind: ErrorKind::Permanent,
+                }.into())
+            }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
+                error!(
+                    "Error sending {} data through recommendation_crawl::crawl_recommendations_thread sender: {}",
+                    features_csv.track_id,
+                    err,
+                );
+            });
+
+            progress.inc(1);
+            telemetry.record_processed("recommendation_crawl", 1);
+        }
+    })
+}
+
+pub fn recommendation_crawl(
+    features_crawled: Receiver<FeaturesCsv>,
+    window: f32,
+    samples_per_track: u32,
+    known_tracks: Arc<CHashMap<TrackId, ()>>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    sender: Sender<RecommendationCsv>,
+) -> thread::Result<()> {
+    let progress = Arc::new(ProgressBar::new(
+        (lines_from_file("features_crawled.csv")
+         .expect("Error in reading features crawled")
+         .len() - 1) as u64
+    ));
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
+    );
+
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let num_threads = num_cpus::get();
+    info!("Using {} threads", num_threads);
+
+    let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
+        crawl_recommendations_thread(
+            features_crawled.clone(),
+            window,
+            samples_per_track,
+            known_tracks.clone(),
+            client_ring.clone(),
+            sender.clone(),
+            progress.clone(),
+            telemetry.clone(),
+        )
+    }).collect();
+
+    threads.into_iter().map(|join_handle| {
+        join_handle.join()
+    }).collect::<thread::Result<()>>().and_then(|res| {
+        progress.finish_with_message("Done crawling recommendations");
+        Ok(res)
+    })
+}
+
+#[allow(dead_code)]
+pub fn recommendation_crawl_main(
+    window: f32,
+    samples_per_track: u32,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let (features_sender, features_receiver) = channel::unbounded();
+    let (recommendation_sender, recommendation_receiver) = channel::unbounded();
+
+    // only recommendations outside the existing dataset are written
+    let known_tracks = Arc::new(CHashMap::new());
+    structs_from_file::<FeaturesCsv>("features_crawled.csv")
+        .expect("Error in reading features crawled").into_iter().map(|features_csv| {
+            known_tracks.insert(features_csv.track_id, ());
+        }).last();
+
+    telemetry.register_queue("recommendation_crawl_input", features_receiver.clone());
+    telemetry.register_queue("recommendation_crawl_output", recommendation_receiver.clone());
+
+    let reader_thread = thread::spawn(move || {
+        read_csv_into_sender(features_sender, "features_crawled.csv")
+            .expect("Error in reading features crawled")
+    });
+
+    let crawler_thread = thread::spawn(move || {
+        recommendation_crawl(
+            features_receiver,
+            window,
+            samples_per_track,
+            known_tracks,
+            client_ring,
+            recommendation_sender,
+        ).expect("Error in crawling recommendations");
+    });
+
+    let writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(recommendation_receiver, "recommendations_crawled.csv")
+            .expect("Error in writing recommendations");
+    });
+
+    reader_thread.join().unwrap_or_else(|err| {
+        error!("Error in recommendation reader thread: {:?}", err);
+    });
+
+    crawler_thread.join().unwrap_or_else(|err| {
+        error!("Error in recommendation crawler thread: {:?}", err);
+    });
+
+    writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in recommendation writer thread: {:?}", err);
+    });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("recommendation_crawl").unwrap_or_else(|err| {
+        error!("Error in writing recommendation_crawl client stats: {}", err);
+    });
+}
@@ -0,0 +1,61 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    ids::{
+        TrackId,
+    },
+    track_types::{
+        TrackSimple,
+    },
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecommendationSeed {
+    #[serde(rename = "afterFilteringSize")]
+    pub after_filtering_size: i32,
+    #[serde(rename = "afterRelinkingSize")]
+    pub after_relinking_size: i32,
+    pub href: Option<String>,
+    pub id: String,
+    #[serde(rename = "initialPoolSize")]
+    pub initial_pool_size: i32,
+    #[serde(rename = "type")]
+    pub object_type: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Recommendations {
+    pub seeds: Vec<RecommendationSeed>,
+    pub tracks: Vec<TrackSimple>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct RecommendationCsv {
+    pub seed_track_id: TrackId,
+    pub track_id: TrackId,
+    pub track_name: String,
+    pub artist_ids: String,
+    pub duration_ms: i32,
+    pub explicit: bool,
+}
+
+impl RecommendationCsv {
+    pub fn extract_from(
+        track_simple: TrackSimple,
+        seed_track_id: TrackId,
+    ) -> Self {
+        Self {
+            seed_track_id: seed_track_id,
+            track_id: track_simple.id,
+            track_name: track_simple.name,
+            artist_ids: track_simple.artists.into_iter().map(|artist_simple| {
+                artist_simple.id.to_string()
+            }).collect::<Vec<String>>().join(", "),
+            duration_ms: track_simple.duration_ms,
+            explicit: track_simple.explicit,
+        }
+    }
+}
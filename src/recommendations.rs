@@ -0,0 +1,181 @@
+use std::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use futures::{
+    future,
+    Future,
+};
+use reqwest::{
+    Url,
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        GenresEnvelope,
+    },
+    ids::{
+        ArtistId,
+        join_ids,
+        TrackId,
+    },
+    market::{
+        Market,
+    },
+    recommendation_types::{
+        Recommendations,
+    },
+    utils::{
+        ErrorKind,
+        get_field_with_retry,
+        get_with_retry,
+        SimpleError,
+    },
+};
+
+type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
+
+// The audio attributes of AudioFeatures (plus popularity) that recommendations
+// can be tuned on.
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug)]
+pub enum TunableAttribute {
+    Acousticness,
+    Danceability,
+    DurationMs,
+    Energy,
+    Instrumentalness,
+    Key,
+    Liveness,
+    Loudness,
+    Mode,
+    Popularity,
+    Speechiness,
+    Tempo,
+    TimeSignature,
+    Valence,
+}
+
+impl TunableAttribute {
+    pub fn name(
+        &self,
+    ) -> &'static str {
+        match self {
+            TunableAttribute::Acousticness => "acousticness",
+            TunableAttribute::Danceability => "danceability",
+            TunableAttribute::DurationMs => "duration_ms",
+            TunableAttribute::Energy => "energy",
+            TunableAttribute::Instrumentalness => "instrumentalness",
+            TunableAttribute::Key => "key",
+            TunableAttribute::Liveness => "liveness",
+            TunableAttribute::Loudness => "loudness",
+            TunableAttribute::Mode => "mode",
+            TunableAttribute::Popularity => "popularity",
+            TunableAttribute::Speechiness => "speechiness",
+            TunableAttribute::Tempo => "tempo",
+            TunableAttribute::TimeSignature => "time_signature",
+            TunableAttribute::Valence => "valence",
+        }
+    }
+
+    fn format_value(
+        &self,
+        value: f32,
+    ) -> String {
+        match self {
+            TunableAttribute::DurationMs |
+            TunableAttribute::Key |
+            TunableAttribute::Mode |
+            TunableAttribute::Popularity |
+            TunableAttribute::TimeSignature => (value.round() as i64).to_string(),
+            _ => value.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AttributeRange {
+    pub min: Option<f32>,
+    pub max: Option<f32>,
+    pub target: Option<f32>,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct RecommendationsQuery {
+    pub seed_artists: Vec<ArtistId>,
+    pub seed_genres: Vec<String>,
+    pub seed_tracks: Vec<TrackId>,
+    pub attributes: Vec<(TunableAttribute, AttributeRange)>,
+    pub limit: Option<u32>,
+    pub market: Option<Market>,
+}
+
+impl RecommendationsQuery {
+    pub fn url(
+        &self,
+    ) -> Result<String, SimpleError> {
+        let num_seeds = self.seed_artists.len() + self.seed_genres.len() + self.seed_tracks.len();
+        if num_seeds == 0 || num_seeds > 5 {
+            return Err(SimpleError {
+                message: format!("Recommendations take 1 to 5 seeds in total, got {}", num_seeds),
+                kind: ErrorKind::Permanent,
+            });
+        }
+
+        let mut params = Vec::new();
+        if !self.seed_artists.is_empty() {
+            params.push(("seed_artists".to_string(), join_ids(&self.seed_artists)));
+        }
+        if !self.seed_genres.is_empty() {
+            params.push(("seed_genres".to_string(), self.seed_genres.join(",")));
+        }
+        if !self.seed_tracks.is_empty() {
+            params.push(("seed_tracks".to_string(), join_ids(&self.seed_tracks)));
+        }
+        self.attributes.iter().map(|(attribute, range)| {
+            vec![("min", range.min), ("max", range.max), ("target", range.target)].into_iter()
+                .map(|(bound, value)| {
+                    value.map(|value| {
+                        params.push((
+                            format!("{}_{}", bound, attribute.name()),
+                            attribute.format_value(value),
+                        ));
+                    });
+                }).last();
+        }).last();
+        self.limit.map(|limit| params.push(("limit".to_string(), limit.to_string())));
+        self.market.as_ref().map(|market| params.push(("market".to_string(), market.to_string())));
+
+        Url::parse_with_params("https://api.spotify.com/v1/recommendations", params).map(|url| {
+            url.into_string()
+        }).map_err(|err| SimpleError {
+            message: err.to_string(),
+            kind: ErrorKind::Permanent,
+        })
+    }
+}
+
+pub fn get_available_genre_seeds(
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> CustomFuture<Vec<String>> {
+    get_field_with_retry::<GenresEnvelope<_>>(
+        "https://api.spotify.com/v1/recommendations/available-genre-seeds/".to_string(),
+        client_ring,
+    )
+}
+
+pub fn get_recommendations(
+    client_ring: Arc<RwLock<ClientRing>>,
+    query: RecommendationsQuery,
+) -> CustomFuture<Recommendations> {
+    match query.url() {
+        Ok(url) => get_with_retry(url, client_ring),
+        Err(err) => Box::new(future::err(Box::new(err))),
+    }
+}
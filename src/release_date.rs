@@ -0,0 +1,248 @@
+use std::{
+    cmp::{
+        self,
+        Ordering,
+    },
+    fmt::{
+        self,
+        Display,
+        Formatter,
+    },
+    str::{
+        FromStr,
+    },
+};
+
+use serde::{
+    de::{
+        self,
+        Deserializer,
+    },
+    ser::{
+        Serializer,
+    },
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    api_enums::{
+        ReleaseDatePrecision,
+    },
+    utils::{
+        ErrorKind,
+        SimpleError,
+    },
+};
+
+fn days_in_month(
+    year: i32,
+    month: u32,
+) -> u32 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// A release date as precise as the API knows it: 2019, 2019-03 or
+// 2019-03-15. Dates order by year, then month, then day, with a less precise
+// date before the more precise ones it covers.
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct ReleaseDate {
+    year: i32,
+    month: Option<u32>,
+    day: Option<u32>,
+}
+
+impl ReleaseDate {
+    pub fn parse(
+        input: &str,
+    ) -> Result<Self, SimpleError> {
+        let input = input.trim();
+        let invalid = || SimpleError {
+            message: format!("{} is not a YYYY, YYYY-MM or YYYY-MM-DD release date", input),
+            kind: ErrorKind::Permanent,
+        };
+        let parse_part = |part: &str, len: usize| {
+            if part.len() == len && part.chars().all(|c| c.is_ascii_digit()) {
+                part.parse::<u32>().map_err(|_| invalid())
+            } else {
+                Err(invalid())
+            }
+        };
+
+        let mut parts = input.split('-');
+        let year = parse_part(parts.next().unwrap_or(""), 4)? as i32;
+        let month = parts.next().map(|month| parse_part(month, 2)).transpose()?;
+        let day = parts.next().map(|day| parse_part(day, 2)).transpose()?;
+        if parts.next().is_some() {
+            return Err(invalid());
+        }
+
+        match (month, day) {
+            (Some(month), _) if month < 1 || month > 12 => Err(invalid()),
+            (Some(month), Some(day)) if day < 1 || day > days_in_month(year, month) => Err(invalid()),
+            _ => Ok(Self {
+                year: year,
+                month: month,
+                day: day,
+            }),
+        }
+    }
+
+    pub fn year(
+        &self,
+    ) -> i32 {
+        self.year
+    }
+
+    pub fn month(
+        &self,
+    ) -> Option<u32> {
+        self.month
+    }
+
+    #[allow(dead_code)]
+    pub fn day(
+        &self,
+    ) -> Option<u32> {
+        self.day
+    }
+
+    // 1994 is in the 1990s
+    pub fn decade(
+        &self,
+    ) -> i32 {
+        self.year - self.year.rem_euclid(10)
+    }
+
+    pub fn precision(
+        &self,
+    ) -> ReleaseDatePrecision {
+        match (self.month, self.day) {
+            (Some(_), Some(_)) => ReleaseDatePrecision::Day,
+            (Some(_), None) => ReleaseDatePrecision::Month,
+            _ => ReleaseDatePrecision::Year,
+        }
+    }
+
+    fn num_parts(
+        &self,
+    ) -> usize {
+        match (self.month, self.day) {
+            (Some(_), Some(_)) => 3,
+            (Some(_), None) => 2,
+            _ => 1,
+        }
+    }
+
+    fn truncated(
+        &self,
+        num_parts: usize,
+    ) -> Self {
+        Self {
+            year: self.year,
+            month: self.month.filter(|_| num_parts >= 2),
+            day: self.day.filter(|_| num_parts >= 3),
+        }
+    }
+
+    // Compares at the precision of the less precise date, so 2019 is equal to
+    // 2019-03-15 but before 2020-01.
+    pub fn cmp_overlapping(
+        &self,
+        other: &Self,
+    ) -> Ordering {
+        let num_parts = cmp::min(self.num_parts(), other.num_parts());
+        self.truncated(num_parts).cmp(&other.truncated(num_parts))
+    }
+}
+
+impl Display for ReleaseDate {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(formatter, "{:04}", self.year)?;
+        if let Some(month) = self.month {
+            write!(formatter, "-{:02}", month)?;
+        }
+        if let Some(day) = self.day {
+            write!(formatter, "-{:02}", day)?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for ReleaseDate {
+    type Err = SimpleError;
+
+    fn from_str(
+        input: &str,
+    ) -> Result<Self, Self::Err> {
+        Self::parse(input)
+    }
+}
+
+impl Serialize for ReleaseDate {
+    fn serialize<S: Serializer>(
+        &self,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string()[..])
+    }
+}
+
+impl<'de> Deserialize<'de> for ReleaseDate {
+    fn deserialize<D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Self, D::Error> {
+        let input = String::deserialize(deserializer)?;
+        Self::parse(&input[..]).map_err(de::Error::custom)
+    }
+}
+
+// Inclusive on both ends, either of which may be open. Bounds and dates are
+// compared with ReleaseDate::cmp_overlapping, so an album released in 2019
+// is kept by a range starting 2019-06.
+#[allow(dead_code)]
+#[derive(Clone, Debug, Default)]
+pub struct ReleaseDateRange {
+    pub earliest: Option<ReleaseDate>,
+    pub latest: Option<ReleaseDate>,
+}
+
+#[allow(dead_code)]
+impl ReleaseDateRange {
+    pub fn decade(
+        decade: i32,
+    ) -> Self {
+        Self::years(decade, decade + 9)
+    }
+
+    pub fn years(
+        earliest: i32,
+        latest: i32,
+    ) -> Self {
+        Self {
+            earliest: Some(ReleaseDate {
+                year: earliest,
+                month: None,
+                day: None,
+            }),
+            latest: Some(ReleaseDate {
+                year: latest,
+                month: None,
+                day: None,
+            }),
+        }
+    }
+
+    pub fn contains(
+        &self,
+        release_date: &ReleaseDate,
+    ) -> bool {
+        self.earliest.map_or(true, |earliest| release_date.cmp_overlapping(&earliest) != Ordering::Less)
+            && self.latest.map_or(true, |latest| release_date.cmp_overlapping(&latest) != Ordering::Greater)
+    }
+}
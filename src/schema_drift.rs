@@ -0,0 +1,349 @@
+use std::{
+    iter::{
+        Enumerate,
+    },
+    slice,
+};
+
+use chashmap::{
+    CHashMap,
+};
+use csv::{
+    Writer,
+};
+use serde::{
+    de::{
+        self,
+        DeserializeSeed,
+        Deserializer,
+        IntoDeserializer,
+        MapAccess,
+        SeqAccess,
+        Visitor,
+    },
+    forward_to_deserialize_any,
+    Serialize,
+};
+use serde_json::{
+    map,
+    Value,
+};
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum DriftKind {
+    // in the response but not in our types, e.g. a newly added field
+    Unexpected,
+    // in our types but absent, optional or not, e.g. a removed field
+    Missing,
+    // required by our types but null, e.g. a field being phased out
+    NullWhereRequired,
+}
+
+impl DriftKind {
+    pub fn name(
+        &self,
+    ) -> &'static str {
+        match self {
+            DriftKind::Unexpected => "unexpected",
+            DriftKind::Missing => "missing",
+            DriftKind::NullWhereRequired => "null_where_required",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchemaDriftCsv {
+    pub root_type: String,
+    pub kind: String,
+    pub path: String,
+    pub count: u64,
+    pub example_url: String,
+}
+
+// Tallies fields that do not match our types, per decoded type and path,
+// across the whole run. Paths have array indices collapsed, e.g.
+// items[].album.available_markets.
+pub struct SchemaDrift {
+    counts: CHashMap<(String, DriftKind, String), (u64, String)>,
+}
+
+impl SchemaDrift {
+    pub fn new(
+    ) -> Self {
+        Self {
+            counts: CHashMap::new(),
+        }
+    }
+
+    pub fn record(
+        &self,
+        root_type: &str,
+        kind: DriftKind,
+        path: &str,
+        url: &str,
+    ) {
+        let path = collapse_indices(path);
+        let mut first_seen = false;
+        self.counts.upsert(
+            (root_type.to_string(), kind, path.clone()),
+            || {
+                first_seen = true;
+                (1, url.to_string())
+            },
+            |(count, _)| *count += 1,
+        );
+
+        if first_seen {
+            warn!("Schema drift in {}: {} field {} (e.g. {})", root_type, kind.name(), path, url);
+        }
+    }
+
+    pub fn report(
+        &self,
+    ) -> Vec<SchemaDriftCsv> {
+        let mut report: Vec<SchemaDriftCsv> = self.counts.clone().into_iter().map(|((root_type, kind, path), (count, example_url))| {
+            SchemaDriftCsv {
+                root_type,
+                kind: kind.name().to_string(),
+                path,
+                count,
+                example_url,
+            }
+        }).collect();
+        report.sort_by(|first, second| {
+            (&first.root_type, &first.kind, &first.path).cmp(&(&second.root_type, &second.kind, &second.path))
+        });
+        report
+    }
+
+    pub fn write_report(
+        &self,
+        file_name: &str,
+    ) -> csv::Result<()> {
+        let mut writer = Writer::from_path(file_name)?;
+        self.report().into_iter().map(|row| {
+            writer.serialize(row)
+        }).collect::<csv::Result<()>>()?;
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+// images[3].url becomes images[].url
+fn collapse_indices(
+    path: &str,
+) -> String {
+    let mut collapsed = String::with_capacity(path.len());
+    let mut chars = path.chars().peekable();
+    while let Some(c) = chars.next() {
+        collapsed.push(c);
+        if c == '[' {
+            while chars.peek().map_or(false, |c| c.is_ascii_digit()) {
+                chars.next();
+            }
+        }
+    }
+    collapsed
+}
+
+// alloc::vec::Vec<crate::artist_types::ArtistFull> becomes Vec<ArtistFull>
+pub fn short_type_name(
+    type_name: &str,
+) -> String {
+    let mut short = String::with_capacity(type_name.len());
+    let mut segment = String::new();
+    let mut chars = type_name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            chars.next();
+            segment.clear();
+        } else if c.is_alphanumeric() || c == '_' {
+            segment.push(c);
+        } else {
+            short.push_str(&segment[..]);
+            segment.clear();
+            short.push(c);
+        }
+    }
+    short.push_str(&segment[..]);
+    short
+}
+
+// Deserializes from a parsed body while comparing every object with the
+// fields of the struct decoded from it. Absent fields are tallied all at once
+// and whether or not they are optional, which errors alone cannot do.
+pub struct FieldProbe<'a, 'de> {
+    value: &'de Value,
+    path: String,
+    root_type: &'a str,
+    url: &'a str,
+    schema_drift: &'a SchemaDrift,
+}
+
+impl<'a, 'de> FieldProbe<'a, 'de> {
+    pub fn new(
+        value: &'de Value,
+        prefix: &str,
+        root_type: &'a str,
+        url: &'a str,
+        schema_drift: &'a SchemaDrift,
+    ) -> Self {
+        Self {
+            value: value,
+            path: prefix.to_string(),
+            root_type: root_type,
+            url: url,
+            schema_drift: schema_drift,
+        }
+    }
+
+    fn field_path(
+        &self,
+        field: &str,
+    ) -> String {
+        if self.path.is_empty() {
+            field.to_string()
+        } else {
+            format!("{}.{}", self.path, field)
+        }
+    }
+
+    fn child(
+        &self,
+        value: &'de Value,
+        path: String,
+    ) -> Self {
+        Self {
+            value: value,
+            path: path,
+            root_type: self.root_type,
+            url: self.url,
+            schema_drift: self.schema_drift,
+        }
+    }
+}
+
+impl<'a, 'de> Deserializer<'de> for FieldProbe<'a, 'de> {
+    type Error = serde_json::Error;
+
+    fn deserialize_any<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Array(items) => visitor.visit_seq(ProbeSeq {
+                items: items.iter().enumerate(),
+                probe: self,
+            }),
+            Value::Object(entries) => visitor.visit_map(ProbeMap {
+                entries: entries.iter(),
+                next_value: None,
+                probe: self,
+            }),
+            value => value.deserialize_any(visitor),
+        }
+    }
+
+    fn deserialize_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        fields: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        if let Value::Object(entries) = self.value {
+            fields.iter().filter(|field| !entries.contains_key(**field)).map(|field| {
+                self.schema_drift.record(self.root_type, DriftKind::Missing, &self.field_path(field)[..], self.url);
+            }).last();
+        }
+        self.deserialize_any(visitor)
+    }
+
+    fn deserialize_option<V: Visitor<'de>>(
+        self,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        match self.value {
+            Value::Null => visitor.visit_none(),
+            _ => visitor.visit_some(self),
+        }
+    }
+
+    fn deserialize_newtype_struct<V: Visitor<'de>>(
+        self,
+        _name: &'static str,
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        visitor.visit_newtype_struct(self)
+    }
+
+    // enums are leaves as far as drift goes
+    fn deserialize_enum<V: Visitor<'de>>(
+        self,
+        name: &'static str,
+        variants: &'static [&'static str],
+        visitor: V,
+    ) -> Result<V::Value, Self::Error> {
+        self.value.deserialize_enum(name, variants, visitor)
+    }
+
+    forward_to_deserialize_any! {
+        bool i8 i16 i32 i64 u8 u16 u32 u64 f32 f64 char str string bytes byte_buf
+        unit unit_struct seq tuple tuple_struct map identifier ignored_any
+    }
+}
+
+struct ProbeSeq<'a, 'de> {
+    items: Enumerate<slice::Iter<'de, Value>>,
+    probe: FieldProbe<'a, 'de>,
+}
+
+impl<'a, 'de> SeqAccess<'de> for ProbeSeq<'a, 'de> {
+    type Error = serde_json::Error;
+
+    fn next_element_seed<T: DeserializeSeed<'de>>(
+        &mut self,
+        seed: T,
+    ) -> Result<Option<T::Value>, Self::Error> {
+        match self.items.next() {
+            Some((index, item)) => {
+                let path = format!("{}[{}]", self.probe.path, index);
+                seed.deserialize(self.probe.child(item, path)).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+}
+
+struct ProbeMap<'a, 'de> {
+    entries: map::Iter<'de>,
+    next_value: Option<(&'de String, &'de Value)>,
+    probe: FieldProbe<'a, 'de>,
+}
+
+impl<'a, 'de> MapAccess<'de> for ProbeMap<'a, 'de> {
+    type Error = serde_json::Error;
+
+    fn next_key_seed<K: DeserializeSeed<'de>>(
+        &mut self,
+        seed: K,
+    ) -> Result<Option<K::Value>, Self::Error> {
+        match self.entries.next() {
+            Some((key, value)) => {
+                self.next_value = Some((key, value));
+                seed.deserialize(key.as_str().into_deserializer()).map(Some)
+            },
+            None => Ok(None),
+        }
+    }
+
+    fn next_value_seed<V: DeserializeSeed<'de>>(
+        &mut self,
+        seed: V,
+    ) -> Result<V::Value, Self::Error> {
+        let (key, value) = self.next_value.take().ok_or_else(|| {
+            de::Error::custom("value requested before its key")
+        })?;
+        let path = self.probe.field_path(&key[..]);
+        seed.deserialize(self.probe.child(value, path))
+    }
+}
@@ -0,0 +1,224 @@
+use std::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use futures::{
+    future,
+    Future,
+};
+use reqwest::{
+    Url,
+};
+use serde::{
+    de::{
+        DeserializeOwned,
+    },
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    album_types::{
+        AlbumSimple,
+    },
+    artist_types::{
+        ArtistFull,
+    },
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        Paging,
+    },
+    market::{
+        Market,
+    },
+    playlist_types::{
+        PlaylistSimple,
+    },
+    track_types::{
+        TrackFull,
+    },
+    utils::{
+        ErrorKind,
+        get_next_wrapped_paging,
+        get_with_retry,
+        SimpleError,
+    },
+};
+
+type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
+
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SearchType {
+    Album,
+    Artist,
+    Playlist,
+    Track,
+}
+
+impl SearchType {
+    pub fn name(
+        &self,
+    ) -> &'static str {
+        match self {
+            SearchType::Album => "album",
+            SearchType::Artist => "artist",
+            SearchType::Playlist => "playlist",
+            SearchType::Track => "track",
+        }
+    }
+
+    // key of this type's paging in search responses
+    pub fn key(
+        &self,
+    ) -> &'static str {
+        match self {
+            SearchType::Album => "albums",
+            SearchType::Artist => "artists",
+            SearchType::Playlist => "playlists",
+            SearchType::Track => "tracks",
+        }
+    }
+}
+
+#[allow(dead_code)]
+#[derive(Clone, Debug)]
+pub enum SearchFilter {
+    Album(String),
+    Artist(String),
+    Track(String),
+    Year(u16),
+    Years(u16, u16),
+    Genre(String),
+    Isrc(String),
+    Upc(String),
+    // albums released in the past two weeks
+    TagNew,
+    // albums in the lowest 10% of popularity
+    TagHipster,
+}
+
+fn quote_value(
+    value: &str,
+) -> String {
+    let value = value.replace("\"", "");
+    if value.contains(char::is_whitespace) {
+        return format!("\"{}\"", value);
+    }
+    value
+}
+
+impl SearchFilter {
+    pub fn to_query_part(
+        &self,
+    ) -> String {
+        match self {
+            SearchFilter::Album(album) => format!("album:{}", quote_value(album)),
+            SearchFilter::Artist(artist) => format!("artist:{}", quote_value(artist)),
+            SearchFilter::Track(track) => format!("track:{}", quote_value(track)),
+            SearchFilter::Year(year) => format!("year:{}", year),
+            SearchFilter::Years(from, to) => format!("year:{}-{}", from, to),
+            // multi-word genres only match as a quoted phrase
+            SearchFilter::Genre(genre) => format!("genre:\"{}\"", genre.replace("\"", "")),
+            SearchFilter::Isrc(isrc) => format!("isrc:{}", quote_value(isrc)),
+            SearchFilter::Upc(upc) => format!("upc:{}", quote_value(upc)),
+            SearchFilter::TagNew => "tag:new".to_string(),
+            SearchFilter::TagHipster => "tag:hipster".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct SearchQuery {
+    pub keywords: Option<String>,
+    pub filters: Vec<SearchFilter>,
+    pub types: Vec<SearchType>,
+    pub market: Option<Market>,
+    pub limit: Option<u32>,
+    pub offset: Option<u32>,
+}
+
+impl SearchQuery {
+    pub fn keywords(
+        keywords: String,
+        search_type: SearchType,
+    ) -> Self {
+        Self {
+            keywords: Some(keywords),
+            types: vec![search_type],
+            ..Default::default()
+        }
+    }
+
+    pub fn q(
+        &self,
+    ) -> String {
+        self.keywords.iter().cloned().chain(self.filters.iter().map(|filter| {
+            filter.to_query_part()
+        })).collect::<Vec<String>>().join(" ")
+    }
+
+    pub fn url(
+        &self,
+    ) -> Result<String, SimpleError> {
+        let q = self.q();
+        if q.is_empty() || self.types.is_empty() {
+            return Err(SimpleError {
+                message: "Search needs keywords or filters and at least one type".to_string(),
+                kind: ErrorKind::Permanent,
+            });
+        }
+
+        let mut params = vec![
+            ("q", q),
+            ("type", self.types.iter().map(|search_type| {
+                search_type.name()
+            }).collect::<Vec<&str>>().join(",")),
+        ];
+        self.market.as_ref().map(|market| params.push(("market", market.to_string())));
+        self.limit.map(|limit| params.push(("limit", limit.to_string())));
+        self.offset.map(|offset| params.push(("offset", offset.to_string())));
+
+        Url::parse_with_params("https://api.spotify.com/v1/search", params).map(|url| {
+            url.into_string()
+        }).map_err(|err| SimpleError {
+            message: err.to_string(),
+            kind: ErrorKind::Permanent,
+        })
+    }
+}
+
+// Only the types requested in the query are present.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SearchResults {
+    pub albums: Option<Paging<AlbumSimple>>,
+    pub artists: Option<Paging<ArtistFull>>,
+    pub playlists: Option<Paging<PlaylistSimple>>,
+    pub tracks: Option<Paging<TrackFull>>,
+}
+
+pub fn search(
+    client_ring: Arc<RwLock<ClientRing>>,
+    query: SearchQuery,
+) -> CustomFuture<SearchResults> {
+    match query.url() {
+        Ok(url) => get_with_retry(url, client_ring),
+        Err(err) => Box::new(future::err(Box::new(err))),
+    }
+}
+
+// Follows the next URL of one result type's paging.
+pub fn get_next_search_paging<D: 'static + DeserializeOwned>(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (url, search_type): (String, SearchType),
+) -> CustomFuture<Paging<D>> {
+    get_next_wrapped_paging(
+        client_ring,
+        (url, search_type.key().to_string()),
+    )
+}
@@ -0,0 +1,453 @@
+use std::{
+    collections::{
+        HashMap,
+        HashSet,
+    },
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use tokio::{
+    runtime::{
+        current_thread::{
+            Runtime,
+        },
+    },
+};
+
+use unicode_normalization::{
+    char::{
+        is_combining_mark,
+    },
+    UnicodeNormalization,
+};
+
+use crate::{
+    artist::{
+        get_artists,
+    },
+    artist_types::{
+        ArtistFull,
+    },
+    browse::{
+        BrowseOptions,
+        get_categories,
+        get_category_playlists,
+        get_featured_playlists,
+        get_new_releases,
+    },
+    client::{
+        ClientRing,
+    },
+    ids::{
+        ArtistId,
+    },
+    io::{
+        write_csv,
+    },
+    market::{
+        Market,
+    },
+    playlist::{
+        get_playlist_tracks,
+    },
+    playlist_types::{
+        PlaylistItem,
+    },
+    search::{
+        search,
+        SearchFilter,
+        SearchQuery,
+        SearchType,
+    },
+    utils::{
+        ItemLookup,
+        loop_until_ok,
+    },
+};
+
+fn count_artist(
+    artist_counts: &mut HashMap<ArtistId, usize>,
+    artist_id: ArtistId,
+) {
+    *artist_counts.entry(artist_id).or_insert(0) += 1;
+}
+
+pub fn artists_from_ids(
+    artist_ids: Vec<ArtistId>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    rt: &mut Runtime,
+) -> Vec<ArtistFull> {
+    artist_ids.chunks(50).flat_map(|artist_ids_chunk| {
+        rt.block_on(loop_until_ok(
+            &get_artists,
+            client_ring.clone(),
+            artist_ids_chunk.to_vec(),
+        )).unwrap_or_else(|err| {
+            error!("Unexpected error in artist::get_artists: {}", err);
+            ItemLookup::request_failed(artist_ids_chunk.len(), &err)
+        }).into_iter().zip(artist_ids_chunk.iter()).filter_map(|(artist_lookup, artist_id)| {
+            match artist_lookup {
+                ItemLookup::Found(artist_full) => Some(artist_full),
+                ItemLookup::NotFound => {
+                    warn!("Artist {} not found", artist_id);
+                    None
+                },
+                ItemLookup::Malformed(err) => {
+                    warn!("Skipping malformed artist {}: {}", artist_id, err);
+                    None
+                },
+                ItemLookup::RequestFailed(err) => {
+                    warn!("Skipping artist {} of a failed request: {}", artist_id, err);
+                    None
+                },
+            }
+        }).collect::<Vec<ArtistFull>>()
+    }).collect()
+}
+
+// Seeds are the artists appearing most often across new releases, featured
+// playlists and the first playlists of every browse category.
+pub fn seeds_from_browse(
+    options: BrowseOptions,
+    playlists_per_category: usize,
+    num_seeds: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> Vec<ArtistFull> {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+    let mut artist_counts = HashMap::new();
+    let mut playlist_ids = Vec::new();
+
+    let listing_options = BrowseOptions {
+        limit: Some(50),
+        ..options.clone()
+    };
+    let market = options.country.as_ref().and_then(|country| Market::parse(&country[..]).ok());
+    let category_playlist_options = BrowseOptions {
+        limit: Some(playlists_per_category as u32),
+        ..options.clone()
+    };
+
+    rt.block_on(loop_until_ok(
+        &get_new_releases,
+        client_ring.clone(),
+        listing_options.clone(),
+    )).map(|paging| {
+        paging.items.into_iter().map(|album_simple| {
+            album_simple.artists.into_iter().map(|artist_simple| {
+                count_artist(&mut artist_counts, artist_simple.id);
+            }).last();
+        }).last();
+    }).unwrap_or_else(|err| {
+        error!("Unexpected error in browse::get_new_releases: {}", err);
+    });
+
+    rt.block_on(loop_until_ok(
+        &get_featured_playlists,
+        client_ring.clone(),
+        listing_options.clone(),
+    )).map(|featured_playlists| {
+        playlist_ids.extend(featured_playlists.playlists.items.into_iter().map(|playlist_simple| {
+            playlist_simple.id
+        }));
+    }).unwrap_or_else(|err| {
+        error!("Unexpected error in browse::get_featured_playlists: {}", err);
+    });
+
+    let categories = rt.block_on(loop_until_ok(
+        &get_categories,
+        client_ring.clone(),
+        listing_options.clone(),
+    )).map(|paging| {
+        paging.items
+    }).unwrap_or_else(|err| {
+        error!("Unexpected error in browse::get_categories: {}", err);
+        vec![]
+    });
+
+    // not looped until ok: categories without playlists in a market are 404s
+    categories.into_iter().map(|category| {
+        rt.block_on(get_category_playlists(
+            client_ring.clone(),
+            (category.id.clone(), category_playlist_options.clone()),
+        )).map(|paging| {
+            playlist_ids.extend(paging.items.into_iter().map(|playlist_simple| {
+                playlist_simple.id
+            }));
+        }).unwrap_or_else(|err| {
+            warn!("Skipping category {}: {}", category.id, err);
+        });
+    }).last();
+
+    playlist_ids.sort();
+    playlist_ids.dedup();
+    info!("Counting artists across {} browse playlists", playlist_ids.len());
+
+    // nor here: browse playlists that were pulled since are 404s
+    playlist_ids.into_iter().map(|playlist_id| {
+        rt.block_on(get_playlist_tracks(
+            client_ring.clone(),
+            (playlist_id.clone(), market.clone()),
+        )).map(|paging| {
+            paging.items.into_iter().map(|playlist_track| {
+                if let Some(PlaylistItem::Track(track_full)) = playlist_track.track {
+                    track_full.artists.into_iter().map(|artist_simple| {
+                        count_artist(&mut artist_counts, artist_simple.id);
+                    }).last();
+                }
+            }).last();
+        }).unwrap_or_else(|err| {
+            warn!("Skipping playlist {}: {}", playlist_id, err);
+        });
+    }).last();
+
+    let mut ranked_artists: Vec<(ArtistId, usize)> = artist_counts.into_iter().collect();
+    ranked_artists.sort_by(|first, second| {
+        second.1.cmp(&first.1).then(first.0.cmp(&second.0))
+    });
+
+    artists_from_ids(
+        ranked_artists.into_iter().take(num_seeds).map(|(artist_id, _)| artist_id).collect(),
+        client_ring,
+        &mut rt,
+    )
+}
+
+// the search endpoint refuses offsets past this
+const MAX_SEARCH_OFFSET: u32 = 1000;
+
+// Search ranks by relevance, so every hit the search will page to is fetched
+// before ranking by popularity.
+fn top_artists_for_genre(
+    genre: &str,
+    per_genre: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+    rt: &mut Runtime,
+) -> Vec<ArtistFull> {
+    let mut artists: Vec<ArtistFull> = Vec::new();
+    let mut offset = 0;
+
+    while offset < MAX_SEARCH_OFFSET {
+        let query = SearchQuery {
+            filters: vec![SearchFilter::Genre(genre.to_string())],
+            types: vec![SearchType::Artist],
+            limit: Some(50),
+            offset: Some(offset),
+            ..Default::default()
+        };
+
+        let (items, next) = rt.block_on(loop_until_ok(
+            &search,
+            client_ring.clone(),
+            query,
+        )).ok().and_then(|results| results.artists).map(|paging| {
+            (paging.items, paging.next)
+        }).unwrap_or((vec![], None));
+
+        artists.extend(items);
+        if next.is_none() {
+            break;
+        }
+        offset += 50;
+    }
+
+    artists.sort_by(by_popularity);
+    artists.truncate(per_genre);
+    info!("Found {} seed artists for genre {}", artists.len(), genre);
+
+    artists
+}
+
+// Takes the top artists of every genre and interleaves them by rank, so each
+// genre is equally represented at the front of the crawl queue.
+pub fn seeds_from_genres(
+    genres: Vec<String>,
+    per_genre: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> Vec<ArtistFull> {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+
+    let mut artists_per_genre: Vec<_> = genres.iter().map(|genre| {
+        top_artists_for_genre(&genre[..], per_genre, client_ring.clone(), &mut rt).into_iter()
+    }).collect();
+
+    let mut seen = HashSet::new();
+    let mut seeds = Vec::new();
+    (0..per_genre).map(|_| {
+        artists_per_genre.iter_mut().map(|genre_artists| {
+            genre_artists.next().map(|artist_full| {
+                if seen.insert(artist_full.id.clone()) {
+                    seeds.push(artist_full);
+                }
+            });
+        }).last();
+    }).last();
+
+    seeds
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SeedResolutionCsv {
+    pub seed: String,
+    // id, exact, ambiguous, inexact, duplicate or unresolved
+    pub status: String,
+    pub artist_id: Option<ArtistId>,
+    pub artist_name: Option<String>,
+    // id:name:popularity of the considered search hits, |-separated
+    pub candidates: String,
+}
+
+impl SeedResolutionCsv {
+    fn new(
+        seed: &str,
+        status: &str,
+        artist: Option<&ArtistFull>,
+        candidates: &[ArtistFull],
+    ) -> Self {
+        Self {
+            seed: seed.to_string(),
+            status: status.to_string(),
+            artist_id: artist.map(|artist_full| artist_full.id.clone()),
+            artist_name: artist.map(|artist_full| artist_full.name.clone()),
+            candidates: candidates.iter().map(|artist_full| {
+                format!(
+                    "{}:{}:{}",
+                    artist_full.id,
+                    artist_full.name,
+                    artist_full.popularity.unwrap_or(-1),
+                )
+            }).collect::<Vec<String>>().join("|"),
+        }
+    }
+}
+
+// Lowercased, without diacritics or punctuation and with collapsed
+// whitespace, so Guns N' Roses matches Guns N Roses.
+fn normalize_name(
+    name: &str,
+) -> String {
+    name.nfd().filter(|c| !is_combining_mark(*c) && !c.is_ascii_punctuation()).collect::<String>()
+        .to_lowercase().split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn by_popularity(
+    first: &ArtistFull,
+    second: &ArtistFull,
+) -> std::cmp::Ordering {
+    second.popularity.cmp(&first.popularity)
+        .then(second.followers.total.cmp(&first.followers.total))
+}
+
+fn resolve_seed_name(
+    seed: &str,
+    client_ring: Arc<RwLock<ClientRing>>,
+    rt: &mut Runtime,
+) -> (Option<ArtistFull>, SeedResolutionCsv) {
+    let query = SearchQuery {
+        limit: Some(20),
+        ..SearchQuery::keywords(seed.to_string(), SearchType::Artist)
+    };
+    let hits = rt.block_on(loop_until_ok(
+        &search,
+        client_ring,
+        query,
+    )).ok().and_then(|results| results.artists).map(|paging| {
+        paging.items
+    }).unwrap_or_default();
+
+    let normalized_seed = normalize_name(seed);
+    let (mut exact, mut inexact): (Vec<ArtistFull>, Vec<ArtistFull>) = hits.into_iter()
+        .partition(|artist_full| normalize_name(&artist_full.name[..]) == normalized_seed);
+    exact.sort_by(by_popularity);
+    inexact.truncate(5);
+    inexact.sort_by(by_popularity);
+
+    match exact.len() {
+        0 if inexact.is_empty() => (None, SeedResolutionCsv::new(seed, "unresolved", None, &[])),
+        // e.g. Beatles for The Beatles
+        0 => {
+            let report = SeedResolutionCsv::new(seed, "inexact", inexact.first(), &inexact);
+            (inexact.into_iter().next(), report)
+        },
+        1 => {
+            let report = SeedResolutionCsv::new(seed, "exact", exact.first(), &exact);
+            (exact.pop(), report)
+        },
+        _ => {
+            let report = SeedResolutionCsv::new(seed, "ambiguous", exact.first(), &exact);
+            (exact.into_iter().next(), report)
+        },
+    }
+}
+
+// Exact name matches are preferred, falling back to the most popular of the
+// top search hits; every seed gets a line in the resolution report.
+pub fn resolve_seed_artists(
+    seeds: Vec<String>,
+    report_file: &str,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> Vec<ArtistFull> {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+
+    let mut seen_seeds = HashSet::new();
+    let seeds: Vec<String> = seeds.into_iter().map(|seed| {
+        seed.trim().to_string()
+    }).filter(|seed| !seed.is_empty() && seen_seeds.insert(seed.clone())).collect();
+
+    let seed_ids: Vec<ArtistId> = seeds.iter().filter_map(|seed| ArtistId::parse(&seed[..]).ok()).collect();
+    let mut artists_by_id: HashMap<ArtistId, ArtistFull> = artists_from_ids(
+        seed_ids,
+        client_ring.clone(),
+        &mut rt,
+    ).into_iter().map(|artist_full| (artist_full.id.clone(), artist_full)).collect();
+    let mut resolved_ids = HashSet::new();
+
+    let mut reports = Vec::new();
+    let artists: Vec<ArtistFull> = seeds.iter().filter_map(|seed| {
+        let (artist, report) = match ArtistId::parse(&seed[..]).ok() {
+            Some(artist_id) => match artists_by_id.remove(&artist_id) {
+                Some(artist_full) => {
+                    let report = SeedResolutionCsv::new(seed, "id", Some(&artist_full), &[]);
+                    (Some(artist_full), report)
+                },
+                // taken by an earlier ID or link of the same artist
+                None if resolved_ids.contains(&artist_id) => {
+                    (None, SeedResolutionCsv::new(seed, "duplicate", None, &[]))
+                },
+                None => (None, SeedResolutionCsv::new(seed, "unresolved", None, &[])),
+            },
+            None => resolve_seed_name(&seed[..], client_ring.clone(), &mut rt),
+        };
+
+        // the same artist given again, by ID, link or any spelling of its name
+        let (artist, report) = match artist {
+            Some(ref artist_full) if resolved_ids.contains(&artist_full.id) => {
+                (None, SeedResolutionCsv::new(seed, "duplicate", Some(artist_full), &[]))
+            },
+            Some(artist_full) => {
+                resolved_ids.insert(artist_full.id.clone());
+                (Some(artist_full), report)
+            },
+            None => (None, report),
+        };
+
+        if report.status != "id" && report.status != "exact" {
+            warn!("Seed {} is {}", seed, report.status);
+        }
+        reports.push(report);
+        artist
+    }).collect();
+
+    info!("Resolved {} of {} seed artists", artists.len(), seeds.len());
+    write_csv(reports, report_file).unwrap_or_else(|err| {
+        error!("Error in writing seed resolution report: {}", err);
+    });
+
+    artists
+}
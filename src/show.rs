@@ -0,0 +1,110 @@
+use std::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use futures::{
+    Future,
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        EpisodesEnvelope,
+        Paging,
+    },
+    ids::{
+        EpisodeId,
+        join_ids,
+        ShowId,
+    },
+    market::{
+        Market,
+    },
+    show_types::{
+        EpisodeFull,
+        EpisodeShowRef,
+        EpisodeSimple,
+        ShowFull,
+    },
+    utils::{
+        get_field_with_retry,
+        get_with_retry,
+        SimpleError,
+    },
+};
+
+type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
+
+// Shows and episodes take a required market: without one (or a user token)
+// client credentials requests treat them as unavailable.
+
+pub fn get_show(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (show_id, market): (ShowId, Market),
+) -> CustomFuture<ShowFull> {
+    Box::new(
+        get_with_retry(
+            format!("https://api.spotify.com/v1/shows/{}/?market={}", show_id, market),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_show_episodes(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (show_id, market): (ShowId, Market),
+) -> CustomFuture<Paging<EpisodeSimple>> {
+    Box::new(
+        get_with_retry(
+            format!("https://api.spotify.com/v1/shows/{}/episodes/?limit=50&market={}", show_id, market),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_episode(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (episode_id, market): (EpisodeId, Market),
+) -> CustomFuture<EpisodeFull> {
+    Box::new(
+        get_with_retry(
+            format!("https://api.spotify.com/v1/episodes/{}/?market={}", episode_id, market),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_episodes(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (episode_ids, market): (Vec<EpisodeId>, Market),
+) -> CustomFuture<Vec<EpisodeFull>> {
+    get_field_with_retry::<EpisodesEnvelope<_>>(
+        format!(
+            "https://api.spotify.com/v1/episodes/?ids={}&market={}",
+            join_ids(&episode_ids),
+            market,
+        ),
+        client_ring,
+    )
+}
+
+// Just the show behind each episode, null for unknown episodes. Skips
+// decoding the rest of the episode and its show.
+pub fn get_episodes_shows(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (episode_ids, market): (Vec<EpisodeId>, Market),
+) -> CustomFuture<Vec<Option<EpisodeShowRef>>> {
+    get_field_with_retry::<EpisodesEnvelope<_>>(
+        format!(
+            "https://api.spotify.com/v1/episodes/?ids={}&market={}",
+            join_ids(&episode_ids),
+            market,
+        ),
+        client_ring,
+    )
+}
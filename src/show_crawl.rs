@@ -0,0 +1,294 @@
+use std::{
+    error::{
+        Error,
+    },
+    sync::{
+        Arc,
+        RwLock,
+    },
+    thread,
+};
+
+use crossbeam_channel::{
+    self as channel,
+    Receiver,
+    Sender,
+};
+use futures::{
+    Future,
+};
+use indicatif::{
+    ProgressBar,
+    ProgressStyle,
+};
+use num_cpus;
+use tokio::{
+    runtime::{
+        current_thread::{
+            Runtime,
+        },
+    },
+};
+
+use crate::{
+    client::{
+        ClientRing,
+    },
+    ids::{
+        EpisodeId,
+        ShowId,
+    },
+    io::{
+        lines_from_file,
+        structs_from_file,
+        write_csv_through_receiver,
+    },
+    market::{
+        Market,
+    },
+    playlist_types::{
+        PlaylistTrackCsv,
+    },
+    show::{
+        get_episodes_shows,
+        get_show,
+    },
+    show_types::{
+        EpisodeCsv,
+        EpisodeSimple,
+        ShowCsv,
+    },
+    telemetry::{
+        Telemetry,
+    },
+    utils::{
+        ErrorKind,
+        get_next_paging,
+        loop_until_ok,
+        SimpleError,
+    },
+};
+
+fn crawl_shows_thread(
+    show_ids: Receiver<ShowId>,
+    market: Market,
+    client_ring: Arc<RwLock<ClientRing>>,
+    show_sender: Sender<ShowCsv>,
+    episode_sender: Sender<EpisodeCsv>,
+    progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
+) -> thread::JoinHandle<()> {
+    thread::spawn(move || {
+        let mut rt = Runtime::new().expect("No tokio runtime");
+
+        while let Some(show_id) = show_ids.recv().ok() {
+            let (mut items, mut next) = rt.block_on(loop_until_ok(
+                &get_show,
+                client_ring.clone(),
+                (show_id.clone(), market.clone()),
+            )).map(|show_full| {
+                show_sender.send(ShowCsv::extract_from(&show_full)).unwrap_or_else(|err| {
+                    error!(
+                        "Error sending {} through show_crawl::crawl_shows_thread sender: {}",
+                        show_id,
+                        err,
+                    );
+                });
+                (Some(show_full.episodes.items), show_full.episodes.next)
+            }).unwrap_or_else(|err| {
+                error!(
+                    "Unexpected error in show::get_show for {}: {}",
+                    show_id,
+                    err,
+                );
+                (None, None)
+            });
+
+            while let Some(paging_items) = items {
+                paging_items.into_iter().map(|episode_simple: EpisodeSimple| {
+                    episode_sender.send(EpisodeCsv::extract_from(
+                        episode_simple,
+                        show_id.clone(),
+                    )).map_err(|err| SimpleError {
+                        message: err.to_string(),
+                        kind: ErrorKind::Permanent,
+                    }.into())
+                }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
+                    error!(
+                        "Error sending {} data through show_crawl::crawl_shows_thread sender: {}",
+                        show_id,
+                        err,
+                    );
+                });
+
+                let (items_new, next_new) = next.map(|next_paging_url| {
+                    rt.block_on(loop_until_ok(
+                        &get_next_paging,
+                        client_ring.clone(),
+                        next_paging_url.clone(),
+                    ).map(|paging| {
+                        (Some(paging.items), paging.next)
+                    })).unwrap_or_else(|err| {
+                        error!(
+                            "Unexpected error in getting next paging with URL {}: {}",
+                            next_paging_url,
+                            err,
+                        );
+                        (None, None)
+                    })
+                }).unwrap_or((None, None));
+
+                items = items_new;
+                next = next_new;
+            }
+
+            progress.inc(1);
+            telemetry.record_processed("show_crawl", 1);
+        }
+    })
+}
+
+pub fn show_crawl(
+    show_ids: Receiver<ShowId>,
+    num_shows: usize,
+    market: Market,
+    client_ring: Arc<RwLock<ClientRing>>,
+    show_sender: Sender<ShowCsv>,
+    episode_sender: Sender<EpisodeCsv>,
+) -> thread::Result<()> {
+    let progress = Arc::new(ProgressBar::new(num_shows as u64));
+    progress.set_style(
+        ProgressStyle::default_bar()
+            .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
+    );
+
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let num_threads = num_cpus::get();
+    info!("Using {} threads", num_threads);
+
+    let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
+        crawl_shows_thread(
+            show_ids.clone(),
+            market.clone(),
+            client_ring.clone(),
+            show_sender.clone(),
+            episode_sender.clone(),
+            progress.clone(),
+            telemetry.clone(),
+        )
+    }).collect();
+
+    threads.into_iter().map(|join_handle| {
+        join_handle.join()
+    }).collect::<thread::Result<()>>().and_then(|res| {
+        progress.finish_with_message("Done crawling shows");
+        Ok(res)
+    })
+}
+
+// Shows of the episodes found by the playlist stage.
+fn show_ids_from_playlist_tracks(
+    market: &Market,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> Vec<ShowId> {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+
+    let episode_ids: Vec<EpisodeId> = structs_from_file::<PlaylistTrackCsv>("playlist_tracks_crawled.csv")
+        .expect("Error in reading playlist tracks crawled").into_iter().filter(|playlist_track_csv| {
+            playlist_track_csv.item_type == "episode"
+        }).filter_map(|playlist_track_csv| {
+            EpisodeId::parse(&playlist_track_csv.item_id[..]).ok()
+        }).collect();
+
+    let mut show_ids: Vec<ShowId> = episode_ids.chunks(50).flat_map(|episode_ids_chunk| {
+        rt.block_on(loop_until_ok(
+            &get_episodes_shows,
+            client_ring.clone(),
+            (episode_ids_chunk.to_vec(), market.clone()),
+        )).unwrap_or_else(|err| {
+            error!("Unexpected error in show::get_episodes_shows: {}", err);
+            vec![]
+        }).into_iter().filter_map(|episode_show| episode_show.map(|episode_show| episode_show.show.id))
+    }).collect();
+
+    show_ids.sort();
+    show_ids.dedup();
+    info!("Found {} shows across {} playlist episodes", show_ids.len(), episode_ids.len());
+
+    show_ids
+}
+
+// Crawls the shows in seed_shows.txt (IDs, URIs or links) and, optionally,
+// the shows behind episodes in playlist_tracks_crawled.csv.
+#[allow(dead_code)]
+pub fn show_crawl_main(
+    market: Market,
+    include_playlist_episodes: bool,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let (show_id_sender, show_id_receiver) = channel::unbounded();
+    let (show_sender, show_receiver) = channel::unbounded();
+    let (episode_sender, episode_receiver) = channel::unbounded();
+
+    let mut show_ids: Vec<ShowId> = lines_from_file("seed_shows.txt").unwrap_or_else(|err| {
+        warn!("No seed shows read: {}", err);
+        vec![]
+    }).into_iter().filter_map(|line| {
+        ShowId::parse(&line[..]).map_err(|err| {
+            warn!("Skipping seed show: {}", err);
+        }).ok()
+    }).collect();
+    if include_playlist_episodes {
+        show_ids.extend(show_ids_from_playlist_tracks(&market, client_ring.clone()));
+    }
+    show_ids.sort();
+    show_ids.dedup();
+
+    let num_shows = show_ids.len();
+    show_ids.into_iter().map(|show_id| {
+        show_id_sender.send(show_id).expect("Error in sending seed show");
+    }).last();
+    drop(show_id_sender);
+
+    telemetry.register_queue("show_crawl_input", show_id_receiver.clone());
+    telemetry.register_queue("show_crawl_output", episode_receiver.clone());
+
+    let crawler_thread = thread::spawn(move || {
+        show_crawl(
+            show_id_receiver,
+            num_shows,
+            market,
+            client_ring,
+            show_sender,
+            episode_sender,
+        ).expect("Error in crawling shows");
+    });
+
+    let show_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(show_receiver, "shows_crawled.csv")
+            .expect("Error in writing shows");
+    });
+
+    let episode_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(episode_receiver, "episodes_crawled.csv")
+            .expect("Error in writing episodes");
+    });
+
+    crawler_thread.join().unwrap_or_else(|err| {
+        error!("Error in show crawler thread: {:?}", err);
+    });
+
+    show_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in show writer thread: {:?}", err);
+    });
+
+    episode_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in episode writer thread: {:?}", err);
+    });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("show_crawl").unwrap_or_else(|err| {
+        error!("Error in writing show_crawl client stats: {}", err);
+    });
+}
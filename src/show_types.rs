@@ -0,0 +1,156 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+
+use crate::{
+    album_types::{
+        Copyright,
+    },
+    common_types::{
+        Image,
+        ObjectId,
+        Paging,
+    },
+    ids::{
+        EpisodeId,
+        ShowId,
+    },
+};
+
+macro_rules! with_show_core_fields {
+    (pub struct $name:ident { $( pub $field:ident: $ty:ty ),* $(,)* }) => {
+        #[derive(Debug, Deserialize, Serialize)]
+        pub struct $name {
+            pub available_markets: Option<Vec<String>>,
+            pub copyrights: Vec<Copyright>,
+            pub description: String,
+            pub explicit: bool,
+            pub external_urls: Map<String, Value>,
+            pub href: String,
+            pub id: ShowId,
+            pub images: Vec<Image>,
+            pub is_externally_hosted: Option<bool>,
+            pub languages: Vec<String>,
+            pub media_type: String,
+            pub name: String,
+            pub publisher: String,
+            pub uri: String,
+            #[serde(rename = "type")]
+            pub object_type: String,
+            $( pub $field: $ty ),*
+        }
+    };
+}
+
+macro_rules! with_episode_core_fields {
+    (pub struct $name:ident { $( pub $field:ident: $ty:ty ),* $(,)* }) => {
+        #[derive(Debug, Deserialize, Serialize)]
+        pub struct $name {
+            pub audio_preview_url: Option<String>,
+            pub description: String,
+            pub duration_ms: i32,
+            pub explicit: bool,
+            pub external_urls: Map<String, Value>,
+            pub href: String,
+            pub id: EpisodeId,
+            pub images: Vec<Image>,
+            pub is_externally_hosted: Option<bool>,
+            pub is_playable: Option<bool>,
+            pub languages: Option<Vec<String>>,
+            pub name: String,
+            pub release_date: String,
+            pub release_date_precision: String,
+            pub resume_point: Option<Map<String, Value>>,
+            pub uri: String,
+            #[serde(rename = "type")]
+            pub object_type: String,
+            $( pub $field: $ty ),*
+        }
+    };
+}
+
+with_show_core_fields!(pub struct ShowSimple {});
+
+with_episode_core_fields!(pub struct EpisodeSimple {});
+
+with_show_core_fields!(pub struct ShowFull {
+    pub episodes: Paging<EpisodeSimple>,
+    pub total_episodes: Option<i32>,
+});
+
+with_episode_core_fields!(pub struct EpisodeFull {
+    pub show: ShowSimple,
+});
+
+// Just enough of an episode to find its show.
+#[derive(Debug, Deserialize)]
+pub struct EpisodeShowRef {
+    pub id: EpisodeId,
+    pub show: ObjectId<ShowId>,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct ShowCsv {
+    pub id: ShowId,
+    pub name: String,
+    pub publisher: String,
+    pub media_type: String,
+    pub explicit: bool,
+    pub languages: String,
+    pub total_episodes: i32,
+    pub is_externally_hosted: Option<bool>,
+}
+
+impl ShowCsv {
+    pub fn extract_from(
+        show_full: &ShowFull,
+    ) -> Self {
+        Self {
+            id: show_full.id.clone(),
+            name: show_full.name.clone(),
+            publisher: show_full.publisher.clone(),
+            media_type: show_full.media_type.clone(),
+            explicit: show_full.explicit,
+            languages: show_full.languages.join(", "),
+            total_episodes: show_full.total_episodes.unwrap_or(show_full.episodes.total),
+            is_externally_hosted: show_full.is_externally_hosted,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct EpisodeCsv {
+    pub show_id: ShowId,
+    pub id: EpisodeId,
+    pub name: String,
+    pub duration_ms: i32,
+    pub explicit: bool,
+    pub languages: String,
+    pub release_date: String,
+    pub release_date_precision: String,
+    pub is_playable: Option<bool>,
+}
+
+impl EpisodeCsv {
+    pub fn extract_from(
+        episode_simple: EpisodeSimple,
+        show_id: ShowId,
+    ) -> Self {
+        Self {
+            show_id: show_id,
+            id: episode_simple.id,
+            name: episode_simple.name,
+            duration_ms: episode_simple.duration_ms,
+            explicit: episode_simple.explicit,
+            languages: episode_simple.languages.unwrap_or_default().join(", "),
+            release_date: episode_simple.release_date,
+            release_date_precision: episode_simple.release_date_precision,
+            is_playable: episode_simple.is_playable,
+        }
+    }
+}
@@ -0,0 +1,323 @@
+use std::{
+    sync::{
+        atomic::{
+            AtomicUsize,
+            Ordering,
+        },
+        Arc,
+        RwLock,
+    },
+    thread,
+    time::{
+        Duration,
+    },
+};
+
+use chashmap::{
+    CHashMap,
+};
+use crossbeam_channel::{
+    Receiver,
+};
+use csv::{
+    Writer,
+};
+use reqwest::{
+    StatusCode,
+};
+use serde::{
+    Serialize,
+};
+
+use crate::{
+    schema_drift::{
+        SchemaDrift,
+    },
+};
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct ClientStats {
+    pub client: String,
+    pub requests: u64,
+    pub successes: u64,
+    pub too_many_requests: u64,
+    pub unauthorized: u64,
+    pub other_statuses: u64,
+    pub transport_errors: u64,
+    pub cooldown_secs: u64,
+    pub latency_total_ms: u64,
+    pub latency_le_100ms: u64,
+    pub latency_le_250ms: u64,
+    pub latency_le_500ms: u64,
+    pub latency_le_1000ms: u64,
+    pub latency_le_2500ms: u64,
+    pub latency_le_5000ms: u64,
+    pub latency_over_5000ms: u64,
+}
+
+impl ClientStats {
+    fn new(
+        client: &str,
+    ) -> Self {
+        Self {
+            client: client.to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn record_latency(
+        &mut self,
+        latency: Duration,
+    ) {
+        let latency_ms = latency.as_secs() * 1000 + latency.subsec_millis() as u64;
+        self.requests += 1;
+        self.latency_total_ms += latency_ms;
+        match latency_ms {
+            0..=100 => self.latency_le_100ms += 1,
+            101..=250 => self.latency_le_250ms += 1,
+            251..=500 => self.latency_le_500ms += 1,
+            501..=1000 => self.latency_le_1000ms += 1,
+            1001..=2500 => self.latency_le_2500ms += 1,
+            2501..=5000 => self.latency_le_5000ms += 1,
+            _ => self.latency_over_5000ms += 1,
+        }
+    }
+
+    pub fn mean_latency_ms(
+        &self,
+    ) -> u64 {
+        if self.requests == 0 {
+            return 0;
+        }
+        self.latency_total_ms / self.requests
+    }
+}
+
+type QueueDepth = Box<Fn() -> usize + Send + Sync>;
+
+// Counters for the whole run, plus counters for the current stage that are
+// cleared every time they are dumped.
+pub struct Telemetry {
+    run: CHashMap<String, ClientStats>,
+    stage: CHashMap<String, ClientStats>,
+    items_processed: CHashMap<String, u64>,
+    queues: RwLock<Vec<(String, QueueDepth)>>,
+    ring_clients: AtomicUsize,
+    ring_cooling_down: AtomicUsize,
+    // off unless enabled, as it wraps every decode
+    schema_drift: RwLock<Option<Arc<SchemaDrift>>>,
+}
+
+impl Telemetry {
+    pub fn new(
+    ) -> Self {
+        Self {
+            run: CHashMap::new(),
+            stage: CHashMap::new(),
+            items_processed: CHashMap::new(),
+            queues: RwLock::new(Vec::new()),
+            ring_clients: AtomicUsize::new(0),
+            ring_cooling_down: AtomicUsize::new(0),
+            schema_drift: RwLock::new(None),
+        }
+    }
+
+    fn update<F: Fn(&mut ClientStats)>(
+        &self,
+        client: &str,
+        update: F,
+    ) {
+        vec![&self.run, &self.stage].into_iter().map(|stats_map| {
+            stats_map.upsert(
+                client.to_string(),
+                || {
+                    let mut stats = ClientStats::new(client);
+                    update(&mut stats);
+                    stats
+                },
+                |stats| update(stats),
+            );
+        }).last();
+    }
+
+    pub fn record_response(
+        &self,
+        client: &str,
+        status: StatusCode,
+        latency: Duration,
+    ) {
+        self.update(client, |stats| {
+            stats.record_latency(latency);
+            match status {
+                StatusCode::OK => stats.successes += 1,
+                StatusCode::TOO_MANY_REQUESTS => stats.too_many_requests += 1,
+                StatusCode::UNAUTHORIZED => stats.unauthorized += 1,
+                _ => stats.other_statuses += 1,
+            }
+        });
+    }
+
+    pub fn record_transport_error(
+        &self,
+        client: &str,
+        latency: Duration,
+    ) {
+        self.update(client, |stats| {
+            stats.record_latency(latency);
+            stats.transport_errors += 1;
+        });
+    }
+
+    pub fn record_cooldown(
+        &self,
+        client: &str,
+        secs: u64,
+    ) {
+        self.update(client, |stats| {
+            stats.cooldown_secs += secs;
+        });
+    }
+
+    pub fn record_processed(
+        &self,
+        stage: &str,
+        count: u64,
+    ) {
+        self.items_processed.upsert(stage.to_string(), || count, |total| *total += count);
+    }
+
+    pub fn register_queue<T: 'static + Send>(
+        &self,
+        name: &str,
+        receiver: Receiver<T>,
+    ) {
+        self.queues.write().expect("telemetry queues RwLock poisoned").push((
+            name.to_string(),
+            Box::new(move || receiver.len()),
+        ));
+    }
+
+    pub fn set_ring_clients(
+        &self,
+        count: usize,
+    ) {
+        self.ring_clients.store(count, Ordering::SeqCst);
+    }
+
+    pub fn cooldown_started(
+        &self,
+    ) {
+        self.ring_cooling_down.fetch_add(1, Ordering::SeqCst);
+    }
+
+    pub fn cooldown_finished(
+        &self,
+    ) {
+        self.ring_cooling_down.fetch_sub(1, Ordering::SeqCst);
+    }
+
+    pub fn enable_schema_drift(
+        &self,
+    ) {
+        let mut schema_drift = self.schema_drift.write().expect("telemetry schema drift RwLock poisoned");
+        if schema_drift.is_none() {
+            *schema_drift = Some(Arc::new(SchemaDrift::new()));
+        }
+    }
+
+    pub fn schema_drift(
+        &self,
+    ) -> Option<Arc<SchemaDrift>> {
+        self.schema_drift.read().expect("telemetry schema drift RwLock poisoned").clone()
+    }
+
+    pub fn run_stats(
+        &self,
+    ) -> Vec<ClientStats> {
+        sorted_stats(&self.run)
+    }
+
+    pub fn items_processed(
+        &self,
+    ) -> Vec<(String, u64)> {
+        let mut items_processed: Vec<(String, u64)> = self.items_processed.clone().into_iter().collect();
+        items_processed.sort();
+        items_processed
+    }
+
+    pub fn queue_depths(
+        &self,
+    ) -> Vec<(String, usize)> {
+        self.queues.read().expect("telemetry queues RwLock poisoned").iter().map(|(name, depth)| {
+            (name.clone(), depth())
+        }).collect()
+    }
+
+    pub fn ring_state(
+        &self,
+    ) -> (usize, usize) {
+        (self.ring_clients.load(Ordering::SeqCst), self.ring_cooling_down.load(Ordering::SeqCst))
+    }
+
+    pub fn log_summary(
+        &self,
+    ) {
+        self.run_stats().into_iter().map(|stats| {
+            info!(
+                "{}: {} requests, {} ok, {} 429s, {} 401s, {} other, {} transport errors, {}s cooldown, {}ms mean latency",
+                stats.client,
+                stats.requests,
+                stats.successes,
+                stats.too_many_requests,
+                stats.unauthorized,
+                stats.other_statuses,
+                stats.transport_errors,
+                stats.cooldown_secs,
+                stats.mean_latency_ms(),
+            );
+        }).last();
+    }
+
+    pub fn spawn_summary_logger(
+        telemetry: Arc<Telemetry>,
+        interval: Duration,
+    ) -> thread::JoinHandle<()> {
+        thread::spawn(move || {
+            loop {
+                thread::sleep(interval);
+                telemetry.log_summary();
+            }
+        })
+    }
+
+    pub fn dump_stage(
+        &self,
+        stage: &str,
+    ) -> csv::Result<()> {
+        let mut writer = Writer::from_path(format!("client_stats_{}.csv", stage))?;
+        sorted_stats(&self.stage).into_iter().map(|stats| {
+            writer.serialize(stats)
+        }).collect::<csv::Result<()>>()?;
+        writer.flush()?;
+
+        // drift is tallied for the whole run, so each dump rewrites the report
+        if let Some(schema_drift) = self.schema_drift() {
+            schema_drift.write_report("schema_drift.csv")?;
+        }
+
+        // the stage is over, so its channels are no longer worth reporting
+        self.stage.clear();
+        self.queues.write().expect("telemetry queues RwLock poisoned").clear();
+        Ok(())
+    }
+}
+
+fn sorted_stats(
+    stats_map: &CHashMap<String, ClientStats>,
+) -> Vec<ClientStats> {
+    let mut stats: Vec<ClientStats> = stats_map.clone().into_iter().map(|(_, stats)| {
+        stats
+    }).collect();
+    stats.sort_by(|first, second| first.client.cmp(&second.client));
+    stats
+}
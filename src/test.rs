@@ -1,8 +1,14 @@
 use std::{
+    cmp::{
+        Ordering,
+    },
     sync::{
         Arc,
         RwLock,
     },
+    time::{
+        Instant,
+    },
 };
 
 use reqwest::{
@@ -10,6 +16,9 @@ use reqwest::{
         Client,
     },
 };
+use serde_json::{
+    Value,
+};
 use tokio::{
     runtime::{
         current_thread::{
@@ -19,12 +28,82 @@ use tokio::{
 };
 
 use crate::{
-    album,
-    artist,
+    album::{
+        self,
+        AlbumTracksOptions,
+    },
+    api_enums::{
+        AlbumGroup,
+        AlbumType,
+        CopyrightType,
+        Key,
+        Mode,
+        ReleaseDatePrecision,
+    },
+    artist::{
+        self,
+        ArtistAlbumsOptions,
+    },
+    artist_types::{
+        ArtistFull,
+    },
+    browse::{
+        self,
+        BrowseOptions,
+    },
     client::{
         ClientRing,
     },
+    common_types::{
+        ArtistsEnvelope,
+        Image,
+        ObjectId,
+    },
+    decode::{
+        decode_at,
+        decode_body,
+        decode_field,
+        DecodeContext,
+        excerpt,
+    },
+    ids::{
+        AlbumId,
+        ArtistId,
+        PlaylistId,
+        TrackId,
+    },
+    market::{
+        Market,
+    },
+    playlist,
+    recommendations::{
+        self,
+        AttributeRange,
+        RecommendationsQuery,
+        TunableAttribute,
+    },
+    release_date::{
+        ReleaseDate,
+        ReleaseDateRange,
+    },
+    schema_drift::{
+        SchemaDrift,
+    },
+    search::{
+        self,
+        SearchFilter,
+        SearchQuery,
+        SearchType,
+    },
+    show,
     track,
+    track_types::{
+        AnalysisAggregates,
+    },
+    user,
+    utils::{
+        ItemLookup,
+    },
 };
 
 #[allow(dead_code, unused_variables)]
@@ -33,87 +112,255 @@ pub fn test_endpoints(
     client_ring: Arc<RwLock<ClientRing>>,
 ) {
     let mut rt = Runtime::new().expect("No tokio runtime");
-    
+    let us = Some(Market::Country("US".to_string()));
+
     let album_data = rt.block_on(album::get_album(
         client_ring.clone(),
-        "0sNOF9WDwhWunNAHPD3Baj".to_string(),
+        ("0sNOF9WDwhWunNAHPD3Baj".parse().expect("Invalid Spotify ID"), None),
     )).expect("Error in album::get_album");
 
     let album_tracks = rt.block_on(album::get_album_tracks(
         client_ring.clone(),
-        "6akEvsycLGftJxYudPjmqK".to_string(),
+        (
+            "6akEvsycLGftJxYudPjmqK".parse().expect("Invalid Spotify ID"),
+            AlbumTracksOptions {
+                limit: Some(10),
+                offset: Some(5),
+                market: us.clone(),
+            },
+        ),
     )).expect("Error in album::get_album_tracks");
 
+    let album_tracklist = rt.block_on(album::get_album_tracklist(
+        client_ring.clone(),
+        ("6akEvsycLGftJxYudPjmqK".parse().expect("Invalid Spotify ID"), us.clone()),
+    )).expect("Error in album::get_album_tracklist");
+    assert_eq!(album_tracklist.len() as i32, album_tracks.total);
+
     let albums = rt.block_on(album::get_albums(
         client_ring.clone(),
-        vec![
-            "41MnTivkwTO3UUJ8DrqEJJ".to_string(),
-            "6JWc4iAiJ9FjyK0B59ABb4".to_string(),
-            "6UXCm6bOO4gFlDQZV5yL37".to_string(),
-        ],
+        (
+            vec![
+                "41MnTivkwTO3UUJ8DrqEJJ".parse().expect("Invalid Spotify ID"),
+                "6JWc4iAiJ9FjyK0B59ABb4".parse().expect("Invalid Spotify ID"),
+                "6UXCm6bOO4gFlDQZV5yL37".parse().expect("Invalid Spotify ID"),
+            ],
+            us.clone(),
+        ),
     )).expect("Error in album::get_albums");
 
     let artist_data = rt.block_on(artist::get_artist(
         client_ring.clone(),
-        "0OdUWJ0sBjDrqHygGUXeCF".to_string(),
+        "0OdUWJ0sBjDrqHygGUXeCF".parse().expect("Invalid Spotify ID"),
     )).expect("Error in artist::get_artist");
 
     let artist_albums = rt.block_on(artist::get_artist_albums(
         client_ring.clone(),
-        "0OdUWJ0sBjDrqHygGUXeCF".to_string(),
+        (
+            "0OdUWJ0sBjDrqHygGUXeCF".parse().expect("Invalid Spotify ID"),
+            ArtistAlbumsOptions {
+                include_groups: vec![AlbumGroup::Album, AlbumGroup::AppearsOn],
+                limit: Some(50),
+                market: us.clone(),
+            },
+        ),
     )).expect("Error in artist::get_artist_albums");
 
     let artist_top_tracks = rt.block_on(artist::get_artist_top_tracks(
         client_ring.clone(),
-        "43ZHCT0cAZBISjO8DG9PnE".to_string(),
+        (
+            "43ZHCT0cAZBISjO8DG9PnE".parse().expect("Invalid Spotify ID"),
+            Market::Country("US".to_string()),
+        ),
     )).expect("Error in artist::get_artist_top_tracks");
 
     let artist_related_artists = rt.block_on(artist::get_artist_related_artists(
         client_ring.clone(),
-        "43ZHCT0cAZBISjO8DG9PnE".to_string(),
+        "43ZHCT0cAZBISjO8DG9PnE".parse().expect("Invalid Spotify ID"),
     )).expect("Error in artist::get_artist_related_artists");
 
     let artists = rt.block_on(artist::get_artists(
         client_ring.clone(),
         vec![
-            "0oSGxfWSnnOXhD2fKuz2Gy".to_string(),
-            "3dBVyJ7JuOMt4GE9607Qin".to_string(),
+            "0oSGxfWSnnOXhD2fKuz2Gy".parse().expect("Invalid Spotify ID"),
+            "3dBVyJ7JuOMt4GE9607Qin".parse().expect("Invalid Spotify ID"),
+            "0000000000000000000000".parse().expect("Invalid Spotify ID"),
         ],
     )).expect("Error in artist::get_artists");
+    assert_eq!(artists.len(), 3);
+    match artists[2] {
+        ItemLookup::NotFound => {},
+        ref artist_lookup => panic!("Expected unknown artist to be missing, got {:?}", artist_lookup),
+    }
 
     let track_analysis = rt.block_on(track::get_track_analysis(
         client_ring.clone(),
-        "3JIxjvbbDrA9ztYlNcp3yL".to_string(),
+        "3JIxjvbbDrA9ztYlNcp3yL".parse().expect("Invalid Spotify ID"),
     )).expect("Error in track::get_track_analysis");
 
+    let track_analysis_summary = rt.block_on(track::get_track_analysis_summary(
+        client_ring.clone(),
+        (
+            "3JIxjvbbDrA9ztYlNcp3yL".parse().expect("Invalid Spotify ID"),
+            AnalysisAggregates {
+                pitches: true,
+                timbre: true,
+                adjust_pitches: true,
+            },
+        ),
+    )).expect("Error in track::get_track_analysis_summary");
+    assert_eq!(track_analysis_summary.num_segments, track_analysis.segments.len());
+    assert_eq!(track_analysis_summary.num_sections, track_analysis.sections.len());
+    assert_eq!(track_analysis_summary.num_tatums, track_analysis.tatums.len());
+
     let track_features = rt.block_on(track::get_track_features(
         client_ring.clone(),
-        "06AKEBrKUckW0KREUWRnvT".to_string(),
+        "06AKEBrKUckW0KREUWRnvT".parse().expect("Invalid Spotify ID"),
     )).expect("Error in track::get_track_features");
 
     let tracks_features = rt.block_on(track::get_tracks_features(
         client_ring.clone(),
         vec![
-            "4JpKVNYnVcJ8tuMKjAj50A".to_string(),
-            "2NRANZE9UCmPAS5XVbXL40".to_string(),
-            "24JygzOLM0EmRQeGtFcIcG".to_string(),
+            "4JpKVNYnVcJ8tuMKjAj50A".parse().expect("Invalid Spotify ID"),
+            "2NRANZE9UCmPAS5XVbXL40".parse().expect("Invalid Spotify ID"),
+            "24JygzOLM0EmRQeGtFcIcG".parse().expect("Invalid Spotify ID"),
         ],
     )).expect("Error in track::get_tracks_features");
 
     let tracks = rt.block_on(track::get_tracks(
         client_ring.clone(),
-        vec![
-            "11dFghVXANMlKmJXsNCbNl".to_string(),
-            "20I6sIOMTCkB6w7ryavxtO".to_string(),
-            "7xGfFoTpQ2E7fRF5lN10tr".to_string(),
-        ],
+        (
+            vec![
+                "11dFghVXANMlKmJXsNCbNl".parse().expect("Invalid Spotify ID"),
+                "20I6sIOMTCkB6w7ryavxtO".parse().expect("Invalid Spotify ID"),
+                "7xGfFoTpQ2E7fRF5lN10tr".parse().expect("Invalid Spotify ID"),
+            ],
+            us.clone(),
+        ),
     )).expect("Error in track::get_tracks");
 
     let track_data = rt.block_on(track::get_track(
         client_ring.clone(),
-        "11dFghVXANMlKmJXsNCbNl".to_string(),
+        ("11dFghVXANMlKmJXsNCbNl".parse().expect("Invalid Spotify ID"), us.clone()),
     )).expect("Error in track::get_track");
 
+    let playlist_data = rt.block_on(playlist::get_playlist(
+        client_ring.clone(),
+        ("37i9dQZF1DXcBWIGoYBM5M".parse().expect("Invalid Spotify ID"), None),
+    )).expect("Error in playlist::get_playlist");
+
+    let playlist_tracks = rt.block_on(playlist::get_playlist_tracks(
+        client_ring.clone(),
+        ("37i9dQZF1DXcBWIGoYBM5M".parse().expect("Invalid Spotify ID"), us.clone()),
+    )).expect("Error in playlist::get_playlist_tracks");
+
+    let user_playlists = rt.block_on(playlist::get_user_playlists(
+        client_ring.clone(),
+        "spotify".to_string(),
+    )).expect("Error in playlist::get_user_playlists");
+
+    let user_profile = rt.block_on(user::get_user(
+        client_ring.clone(),
+        "spotify".to_string(),
+    )).expect("Error in user::get_user");
+
+    let show_data = rt.block_on(show::get_show(
+        client_ring.clone(),
+        (
+            "2mTUnDkuKUkhiueKcVWoP0".parse().expect("Invalid Spotify ID"),
+            Market::Country("US".to_string()),
+        ),
+    )).expect("Error in show::get_show");
+
+    let show_episodes = rt.block_on(show::get_show_episodes(
+        client_ring.clone(),
+        (
+            "2mTUnDkuKUkhiueKcVWoP0".parse().expect("Invalid Spotify ID"),
+            Market::Country("US".to_string()),
+        ),
+    )).expect("Error in show::get_show_episodes");
+
+    let episode_data = rt.block_on(show::get_episode(
+        client_ring.clone(),
+        (
+            "512ojhOuo1ktJprKbVcKyQ".parse().expect("Invalid Spotify ID"),
+            Market::Country("US".to_string()),
+        ),
+    )).expect("Error in show::get_episode");
+
+    let episodes = rt.block_on(show::get_episodes(
+        client_ring.clone(),
+        (
+            vec![
+                "77o6BIVlYM3msb4MMIL1jH".parse().expect("Invalid Spotify ID"),
+                "0Q86acNRm6V9GYx55SXKwf".parse().expect("Invalid Spotify ID"),
+            ],
+            Market::Country("US".to_string()),
+        ),
+    )).expect("Error in show::get_episodes");
+
+    let episodes_shows = rt.block_on(show::get_episodes_shows(
+        client_ring.clone(),
+        (
+            vec![
+                "77o6BIVlYM3msb4MMIL1jH".parse().expect("Invalid Spotify ID"),
+                "0Q86acNRm6V9GYx55SXKwf".parse().expect("Invalid Spotify ID"),
+            ],
+            Market::Country("US".to_string()),
+        ),
+    )).expect("Error in show::get_episodes_shows");
+
+    let browse_options = BrowseOptions {
+        country: Some("US".to_string()),
+        locale: Some("en_US".to_string()),
+        limit: Some(10),
+    };
+
+    let categories = rt.block_on(browse::get_categories(
+        client_ring.clone(),
+        browse_options.clone(),
+    )).expect("Error in browse::get_categories");
+
+    let category_playlists = rt.block_on(browse::get_category_playlists(
+        client_ring.clone(),
+        ("toplists".to_string(), browse_options.clone()),
+    )).expect("Error in browse::get_category_playlists");
+
+    let new_releases = rt.block_on(browse::get_new_releases(
+        client_ring.clone(),
+        browse_options.clone(),
+    )).expect("Error in browse::get_new_releases");
+
+    let featured_playlists = rt.block_on(browse::get_featured_playlists(
+        client_ring.clone(),
+        browse_options.clone(),
+    )).expect("Error in browse::get_featured_playlists");
+
+    let genre_seeds = rt.block_on(recommendations::get_available_genre_seeds(
+        client_ring.clone(),
+    )).expect("Error in recommendations::get_available_genre_seeds");
+
+    let recommendations = rt.block_on(recommendations::get_recommendations(
+        client_ring.clone(),
+        RecommendationsQuery {
+            seed_artists: vec!["43ZHCT0cAZBISjO8DG9PnE".parse().expect("Invalid Spotify ID")],
+            seed_genres: vec!["rock".to_string()],
+            attributes: vec![
+                (TunableAttribute::Energy, AttributeRange {
+                    min: Some(0.4),
+                    max: Some(0.9),
+                    target: Some(0.7),
+                }),
+                (TunableAttribute::Tempo, AttributeRange {
+                    target: Some(120.0),
+                    ..Default::default()
+                }),
+            ],
+            limit: Some(10),
+            ..Default::default()
+        },
+    )).expect("Error in recommendations::get_recommendations");
+
     println!("Endpoint tests passed!");
 }
 
@@ -140,4 +387,232 @@ pub fn test_searches(
     ).expect("Error in track::search_tracks");
 
     println!("Shirim tracks:\n{:#?}", track_results.items[0]);
+
+    let filtered_results = rt.block_on(search::search(
+        client_ring.clone(),
+        SearchQuery {
+            filters: vec![
+                SearchFilter::Artist("Car Seat Headrest".to_string()),
+                SearchFilter::Years(2010, 2019),
+            ],
+            types: vec![SearchType::Album, SearchType::Track],
+            market: Some(Market::Country("US".to_string())),
+            limit: Some(5),
+            ..Default::default()
+        },
+    )).expect("Error in search::search");
+
+    println!(
+        "Car Seat Headrest 2010s albums and tracks: {:?} {:?}",
+        filtered_results.albums.map(|paging| paging.total),
+        filtered_results.tracks.map(|paging| paging.total),
+    );
+
+    let encoded_results = rt.block_on(
+        artist::search_artists(client_ring.clone(), "Simon & Garfunkel".to_string())
+    ).expect("Error in artist::search_artists");
+
+    println!("Simon & Garfunkel artists:\n{:#?}", encoded_results.items[0]);
+}
+
+#[allow(dead_code, unused_variables)]
+pub fn test_user_endpoints(
+    user_client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+
+    let saved_tracks = rt.block_on(user::get_saved_tracks(
+        user_client_ring.clone(),
+        (0, Some(Market::FromToken)),
+    )).expect("Error in user::get_saved_tracks");
+
+    let followed_artists = rt.block_on(user::get_followed_artists(
+        user_client_ring.clone(),
+        None,
+    )).expect("Error in user::get_followed_artists");
+
+    let recently_played = rt.block_on(user::get_recently_played(
+        user_client_ring.clone(),
+        None,
+    )).expect("Error in user::get_recently_played");
+
+    println!("User endpoint tests passed!");
+}
+
+#[allow(dead_code)]
+pub fn test_ids(
+) {
+    let artist_id = ArtistId::parse("43ZHCT0cAZBISjO8DG9PnE").expect("Error in ArtistId::parse");
+    assert_eq!(ArtistId::parse("spotify:artist:43ZHCT0cAZBISjO8DG9PnE").ok(), Some(artist_id.clone()));
+    assert_eq!(
+        ArtistId::parse("https://open.spotify.com/intl-de/artist/43ZHCT0cAZBISjO8DG9PnE?si=abc").ok(),
+        Some(artist_id.clone()),
+    );
+    assert_eq!(artist_id.uri(), "spotify:artist:43ZHCT0cAZBISjO8DG9PnE");
+
+    assert!(AlbumId::parse("spotify:artist:43ZHCT0cAZBISjO8DG9PnE").is_err());
+    assert!(TrackId::parse("https://open.spotify.com/album/0sNOF9WDwhWunNAHPD3Baj").is_err());
+    assert!(PlaylistId::parse("37i9dQZF1DXcBWIGoYBM5").is_err());
+    assert!(PlaylistId::parse("37i9dQZF1DXcBWIGoYBM5-").is_err());
+
+    println!("ID tests passed!");
+}
+
+#[allow(dead_code)]
+pub fn test_api_enums(
+) {
+    assert_eq!(AlbumType::parse("Single"), AlbumType::Single);
+    assert_eq!(AlbumGroup::parse("appears_on").name(), "appears_on");
+    assert_eq!(
+        serde_json::from_str::<AlbumType>(r#""ep""#).ok(),
+        Some(AlbumType::Unknown("ep".to_string())),
+    );
+    assert_eq!(serde_json::to_string(&CopyrightType::Performance).ok(), Some(r#""P""#.to_string()));
+
+    assert_eq!(serde_json::from_str::<Key>("1").ok(), Some(Key::CSharp));
+    assert_eq!(serde_json::from_str::<Key>("-1").ok(), Some(Key::NoKey));
+    assert_eq!(serde_json::from_str::<Key>("12").ok(), Some(Key::Unknown(12)));
+    assert_eq!(serde_json::from_str::<Key>(r#""C#""#).ok(), Some(Key::CSharp));
+    assert_eq!(serde_json::to_string(&Mode::Minor).ok(), Some(r#""minor""#.to_string()));
+    assert_eq!(serde_json::from_str::<Mode>(r#""major""#).ok(), Some(Mode::Major));
+    assert!(serde_json::from_str::<Mode>(r#""lydian""#).is_err());
+
+    println!("API enum tests passed!");
+}
+
+#[allow(dead_code)]
+pub fn test_release_dates(
+) {
+    let year = ReleaseDate::parse("1994").expect("Error in ReleaseDate::parse");
+    let month = ReleaseDate::parse("1994-03").expect("Error in ReleaseDate::parse");
+    let day = ReleaseDate::parse("1994-03-15").expect("Error in ReleaseDate::parse");
+    assert_eq!(year.precision(), ReleaseDatePrecision::Year);
+    assert_eq!(month.precision(), ReleaseDatePrecision::Month);
+    assert_eq!((day.year(), day.month(), day.day()), (1994, Some(3), Some(15)));
+    assert_eq!(day.decade(), 1990);
+    assert_eq!(day.to_string(), "1994-03-15");
+
+    assert!(year < month && month < day);
+    assert!(day < ReleaseDate::parse("1994-04").expect("Error in ReleaseDate::parse"));
+    assert_eq!(year.cmp_overlapping(&day), Ordering::Equal);
+
+    assert!(ReleaseDate::parse("1994-02-29").is_err());
+    assert!(ReleaseDate::parse("2000-02-29").is_ok());
+    assert!(ReleaseDate::parse("1994-13").is_err());
+    assert!(ReleaseDate::parse("94-03-15").is_err());
+    assert!(ReleaseDate::parse("1994-03-15-01").is_err());
+
+    assert!(ReleaseDateRange::decade(1990).contains(&day));
+    assert!(!ReleaseDateRange::decade(2000).contains(&day));
+    let range = ReleaseDateRange {
+        earliest: ReleaseDate::parse("1994-06").ok(),
+        latest: None,
+    };
+    assert!(range.contains(&year));
+    assert!(!range.contains(&month));
+
+    println!("Release date tests passed!");
+}
+
+#[allow(dead_code)]
+pub fn test_decode(
+) {
+    let body = br#"{"images": [{"url": "a"}, {"url": 1}]}"#;
+    let value: Value = decode_body(&body[..], "https://api.spotify.com/v1/browse/categories/", &DecodeContext::default())
+        .expect("Error in decode::decode_body");
+
+    let err = decode_field::<Vec<Image>>(&value, "images", "https://api.spotify.com/v1/browse/categories/", &DecodeContext::default())
+        .err().expect("Expected a decoding error");
+    assert!(err.message.contains("at images[1].url"), "{}", err.message);
+
+    let err = decode_field::<Vec<Image>>(&value, "icons", "https://api.spotify.com/v1/browse/categories/", &DecodeContext::default())
+        .err().expect("Expected a decoding error");
+    assert!(err.message.contains("no icons field"), "{}", err.message);
+
+    let long_body = vec![b'x'; 2000];
+    assert!(excerpt(&long_body[..]).ends_with("... (2000 bytes)"));
+
+    let schema_drift = Arc::new(SchemaDrift::new());
+    let context = DecodeContext {
+        diagnostics_dir: None,
+        schema_drift: Some(schema_drift.clone()),
+        dumped_urls: Default::default(),
+    };
+    let body = br#"{"images": [{"url": "a", "format": "jpeg"}, {"width": 64}, {"url": null}]}"#;
+    let value: Value = decode_body(&body[..], "https://api.spotify.com/v1/browse/categories/", &context)
+        .expect("Error in decode::decode_body");
+    let images = value.get("images").and_then(|images| images.as_array()).expect("Error in fixture format");
+    images.iter().enumerate().map(|(index, image)| {
+        decode_at::<Image>(image, &format!("images[{}]", index)[..], "https://api.spotify.com/v1/browse/categories/", &context).ok();
+    }).last();
+
+    let drift: Vec<(String, String)> = schema_drift.report().into_iter().map(|row| (row.kind, row.path)).collect();
+    assert_eq!(drift, vec![
+        ("missing".to_string(), "images[].height".to_string()),
+        ("missing".to_string(), "images[].url".to_string()),
+        ("missing".to_string(), "images[].width".to_string()),
+        ("null_where_required".to_string(), "images[].url".to_string()),
+        ("unexpected".to_string(), "images[].format".to_string()),
+    ]);
+    let height_count = schema_drift.report().into_iter().find(|row| row.path == "images[].height").map(|row| row.count);
+    assert_eq!(height_count, Some(3));
+
+    println!("Decode tests passed!");
+}
+
+// Decoding a batched get_artists body through serde_json::Value, as endpoints
+// used to, against decoding the envelope directly and against a lightweight
+// envelope of IDs only.
+pub fn bench_decode(
+    iterations: u32,
+) {
+    let artist = r#"{
+        "external_urls": {"spotify": "https://open.spotify.com/artist/0OdUWJ0sBjDrqHygGUXeCF"},
+        "followers": {"href": null, "total": 1063814},
+        "genres": ["indie folk", "indie pop", "indie rock", "stomp and holler"],
+        "href": "https://api.spotify.com/v1/artists/0OdUWJ0sBjDrqHygGUXeCF",
+        "id": "0OdUWJ0sBjDrqHygGUXeCF",
+        "images": [
+            {"height": 816, "url": "https://i.scdn.co/image/eb266625dab075341e8c4378a177a27370f91903", "width": 1000},
+            {"height": 522, "url": "https://i.scdn.co/image/2f91c3cace3c5a6a48f3d0e2fd21364d4911b332", "width": 640},
+            {"height": 163, "url": "https://i.scdn.co/image/2efc93d7ee88435116093274980f04ebceb7b527", "width": 200}
+        ],
+        "name": "Band of Horses",
+        "popularity": 59,
+        "type": "artist",
+        "uri": "spotify:artist:0OdUWJ0sBjDrqHygGUXeCF"
+    }"#;
+    let body = format!(r#"{{"artists": [{}]}}"#, vec![artist; 50].join(","));
+    let body = body.as_bytes();
+
+    let start = Instant::now();
+    (0..iterations).map(|_| {
+        let value: Value = serde_json::from_slice(body).expect("Error in bench fixture format");
+        value.get("artists").and_then(|artists| artists.as_array()).expect("Error in bench fixture format")
+            .iter().map(|value| {
+                serde_json::from_value::<ArtistFull>(value.to_owned()).expect("Error in bench fixture format")
+            }).collect::<Vec<ArtistFull>>()
+    }).last();
+    let through_value = start.elapsed();
+
+    let start = Instant::now();
+    (0..iterations).map(|_| {
+        serde_json::from_slice::<ArtistsEnvelope<Vec<ArtistFull>>>(body).expect("Error in bench fixture format")
+    }).last();
+    let envelope = start.elapsed();
+
+    let start = Instant::now();
+    (0..iterations).map(|_| {
+        serde_json::from_slice::<ArtistsEnvelope<Vec<ObjectId<ArtistId>>>>(body).expect("Error in bench fixture format")
+    }).last();
+    let ids_only = start.elapsed();
+
+    println!(
+        "Decoding {} x {} bytes: {:?} through Value, {:?} as envelope, {:?} as IDs only",
+        iterations,
+        body.len(),
+        through_value,
+        envelope,
+        ids_only,
+    );
 }
@@ -0,0 +1,128 @@
+use std::{
+    collections::{
+        HashMap,
+    },
+    error::{
+        Error,
+    },
+    path::{
+        Path,
+    },
+    time::{
+        SystemTime,
+        UNIX_EPOCH,
+    },
+};
+
+use serde::{
+    Deserialize,
+    Serialize,
+};
+
+use crate::{
+    credentials::{
+        Secret,
+    },
+    io::{
+        structs_from_file,
+        write_private_csv,
+    },
+};
+
+// tokens closer than this to expiring are fetched again instead of reused
+const EXPIRY_MARGIN_SECS: u64 = 60;
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+struct CachedToken {
+    pub client_id: String,
+    pub access_token: Secret,
+    pub expires_at: u64,
+}
+
+pub struct TokenCache {
+    file_name: Option<String>,
+    tokens: HashMap<String, CachedToken>,
+}
+
+pub fn now_secs(
+) -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| {
+        duration.as_secs()
+    }).unwrap_or(0)
+}
+
+impl TokenCache {
+    pub fn load(
+        file_name: &str,
+    ) -> Self {
+        let mut tokens = HashMap::new();
+
+        if Path::new(file_name).exists() {
+            structs_from_file::<CachedToken>(file_name).map(|cached_tokens| {
+                cached_tokens.into_iter().map(|cached_token| {
+                    tokens.insert(cached_token.client_id.clone(), cached_token);
+                }).last();
+            }).unwrap_or_else(|err| {
+                warn!("Ignoring unreadable token cache {}: {}", file_name, err);
+            });
+        }
+
+        Self {
+            file_name: Some(file_name.to_string()),
+            tokens: tokens,
+        }
+    }
+
+    pub fn in_memory(
+    ) -> Self {
+        Self {
+            file_name: None,
+            tokens: HashMap::new(),
+        }
+    }
+
+    pub fn get(
+        &self,
+        client_id: &str,
+    ) -> Option<Secret> {
+        self.tokens.get(client_id).and_then(|cached_token| {
+            if cached_token.expires_at > now_secs() + EXPIRY_MARGIN_SECS {
+                Some(cached_token.access_token.clone())
+            } else {
+                None
+            }
+        })
+    }
+
+    pub fn insert(
+        &mut self,
+        client_id: &str,
+        access_token: Secret,
+        expires_in: i32,
+    ) {
+        self.tokens.insert(client_id.to_string(), CachedToken {
+            client_id: client_id.to_string(),
+            access_token: access_token,
+            expires_at: now_secs() + expires_in.max(0) as u64,
+        });
+    }
+
+    pub fn remove(
+        &mut self,
+        client_id: &str,
+    ) {
+        self.tokens.remove(client_id);
+    }
+
+    pub fn save(
+        &self,
+    ) -> Result<(), Box<dyn Error>> {
+        match self.file_name {
+            Some(ref file_name) => write_private_csv(
+                self.tokens.values().collect::<Vec<&CachedToken>>(),
+                &file_name[..],
+            ),
+            None => Ok(()),
+        }
+    }
+}
@@ -14,25 +14,66 @@ use crate::{
         ClientRing,
     },
     common_types::{
+        AudioFeaturesEnvelope,
         Paging,
+        TracksEnvelope,
+    },
+    decode::{
+        decode_reader_seed,
+    },
+    ids::{
+        join_ids,
+        TrackId,
+    },
+    market::{
+        Market,
+        with_market,
+    },
+    search::{
+        search,
+        SearchQuery,
+        SearchType,
     },
     track_types::{
+        AnalysisAggregates,
+        AnalysisSummary,
+        AnalysisSummarySeed,
         AudioAnalysis,
         AudioFeatures,
         TrackFull,
     },
     utils::{
+        ErrorKind,
+        get_item_lookups_with_retry,
+        get_streamed_with_retry,
         get_with_retry,
-        search,
+        ItemLookup,
         SimpleError,
     },
 };
 
 type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
 
+// Summarized while the body streams in, for stages that only need counts and
+// aggregates of the analysis. Neither the body nor the analysis is held whole.
+pub fn get_track_analysis_summary(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (track_id, aggregates): (TrackId, AnalysisAggregates),
+) -> CustomFuture<AnalysisSummary> {
+    let url = format!("https://api.spotify.com/v1/audio-analysis/{}/", track_id);
+
+    Box::new(
+        get_streamed_with_retry(
+            url.clone(),
+            client_ring,
+            move |reader| decode_reader_seed(AnalysisSummarySeed(aggregates), reader, &url[..]),
+        )
+    )
+}
+
 pub fn get_track_analysis(
     client_ring: Arc<RwLock<ClientRing>>,
-    track_id: String,
+    track_id: TrackId,
 ) -> CustomFuture<AudioAnalysis> {
     Box::new(
         get_with_retry(
@@ -44,7 +85,7 @@ pub fn get_track_analysis(
 
 pub fn get_track_features(
     client_ring: Arc<RwLock<ClientRing>>,
-    track_id: String,
+    track_id: TrackId,
 ) -> CustomFuture<AudioFeatures> {
     Box::new(
         get_with_retry(
@@ -56,56 +97,39 @@ pub fn get_track_features(
 
 pub fn get_tracks_features(
     client_ring: Arc<RwLock<ClientRing>>,
-    track_ids: Vec<String>,
-) -> CustomFuture<Vec<AudioFeatures>> {
-    Box::new(
-        get_with_retry::<serde_json::Value>(
-            format!(
-                "https://api.spotify.com/v1/audio-features/?ids={}",
-                track_ids.join(","),
-            ),
-            client_ring,
-        ).map(|value| {
-            value.get("audio_features").expect("Error in tracks::get_tracks_features format")
-                .as_array().expect("Error in track::get_tracks_features format")
-                .iter().map(|value| {
-                    serde_json::from_value::<AudioFeatures>(value.to_owned())
-                        .expect("Error in track::get_tracks_features format")
-                }).collect()
-        })
+    track_ids: Vec<TrackId>,
+) -> CustomFuture<Vec<ItemLookup<AudioFeatures>>> {
+    get_item_lookups_with_retry::<_, AudioFeaturesEnvelope<_>>(
+        format!(
+            "https://api.spotify.com/v1/audio-features/?ids={}",
+            join_ids(&track_ids),
+        ),
+        track_ids.len(),
+        client_ring,
     )
 }
 
 pub fn get_tracks(
     client_ring: Arc<RwLock<ClientRing>>,
-    track_ids: Vec<String>,
-) -> CustomFuture<Vec<TrackFull>> {
-    Box::new(
-        get_with_retry::<serde_json::Value>(
-            format!(
-                "https://api.spotify.com/v1/tracks/?ids={}",
-                track_ids.join(","),
-            ),
-            client_ring,
-        ).map(|value| {
-            value.get("tracks").expect("Error in track::get_tracks format")
-                .as_array().expect("Error in track::get_tracks format")
-                .iter().map(|value| {
-                    serde_json::from_value::<TrackFull>(value.to_owned())
-                        .expect("Error in track::get_tracks format")
-                }).collect()
-
-        })
+    (track_ids, market): (Vec<TrackId>, Option<Market>),
+) -> CustomFuture<Vec<ItemLookup<TrackFull>>> {
+    get_item_lookups_with_retry::<_, TracksEnvelope<_>>(
+        with_market(format!(
+            "https://api.spotify.com/v1/tracks/?ids={}",
+            join_ids(&track_ids),
+        ), &market),
+        track_ids.len(),
+        client_ring,
     )
 }
 
 pub fn get_track(
     client_ring: Arc<RwLock<ClientRing>>,
-    track_id: String,
+    (track_id, market): (TrackId, Option<Market>),
 ) -> CustomFuture<TrackFull> {
     Box::new(
         get_with_retry(
-            format!("https://api.spotify.com/v1/tracks/{}/", track_id),
+            with_market(format!("https://api.spotify.com/v1/tracks/{}/", track_id), &market),
             client_ring,
         )
     )
@@ -116,12 +140,12 @@ pub fn search_tracks(
     query: String,
 ) -> CustomFuture<Paging<TrackFull>> {
     Box::new(
-        search::<serde_json::Value>(query, "track", client_ring)
-            .map(|value| {
-                serde_json::from_value(
-                    value.get("tracks").expect("Error in track::search_tracks format")
-                        .to_owned()
-                ).expect("Error in track::search_track format")
+        search(client_ring, SearchQuery::keywords(query, SearchType::Track))
+            .and_then(|results| {
+                results.tracks.ok_or(Box::new(SimpleError {
+                    message: "No tracks in track::search_tracks results".to_string(),
+                    kind: ErrorKind::Permanent,
+                }))
             })
     )
 }
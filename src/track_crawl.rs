@@ -40,32 +40,51 @@ use crate::{
     client::{
         ClientRing,
     },
+    common_types::{
+        MissingItemCsv,
+    },
+    ids::{
+        AlbumId,
+    },
     io::{
         lines_from_file,
         read_csv_chunks_into_sender,
         write_csv_through_receiver,
     },
+    market::{
+        Market,
+    },
+    telemetry::{
+        Telemetry,
+    },
     track_types::{
         TrackCsv,
     },
     utils::{
+        ErrorKind,
         get_next_paging,
+        ItemLookup,
         loop_until_ok,
         SimpleError,
     },
 };
 
 struct NextPaging {
-    origin_album: String,
+    origin_album: AlbumId,
     origin_album_genres: String,
+    release_date: String,
+    market: Option<Market>,
     url: String,
 }
 
 fn crawl_albums_tracks_thread(
     albums_crawled: Receiver<Vec<AlbumCsv>>,
+    markets: Vec<Option<Market>>,
     client_ring: Arc<RwLock<ClientRing>>,
     sender: Sender<TrackCsv>,
+    missing_sender: Sender<MissingItemCsv>,
     progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let mut rt = Runtime::new().expect("No tokio runtime");
@@ -73,51 +92,82 @@ fn crawl_albums_tracks_thread(
         while let Some(albums_csv) = albums_crawled.recv().ok() {
             let mut next_pagings = Vec::new();
 
-            let albums_ids = albums_csv.iter().map(|album_csv| {
+            let albums_ids: Vec<AlbumId> = albums_csv.iter().map(|album_csv| {
                 album_csv.id.clone()
             }).collect();
 
-            rt.block_on(loop_until_ok(
-                &get_albums,
-                client_ring.clone(),
-                albums_ids,
-            )).unwrap_or_else(|err| {
-                error!(
-                    "Unexpected error in album::get_albums_loop_until_ok : {}",
-                    err,
-                );
-                vec![]
-            }).into_iter().zip(albums_csv.iter()).map(|(album_full, album_csv)| {
-                let album_id = album_full.id.clone();
-                let mut album_genres = album_csv.origin_artist_genres.clone();
-                if !album_full.genres.is_empty() {
-                    album_genres = album_full.genres.join(", ");
-                }
-
-                album_full.tracks.next.map(|next_url| {
-                    next_pagings.push(NextPaging {
-                        origin_album: album_id.clone(),
-                        origin_album_genres: album_genres.clone(),
-                        url: next_url.clone(),
-                    });
-                });
-                album_full.tracks.items.into_iter().map(|track_simple| {
-                    sender.send(TrackCsv::extract_from(
-                        track_simple,
-                        album_id.clone(),
-                        album_genres.clone(),
-                    )).map_err(|err| SimpleError {
-                        message: err.to_string(),
-                    }.into())
-                }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
+            markets.iter().map(|market| {
+                rt.block_on(loop_until_ok(
+                    &get_albums,
+                    client_ring.clone(),
+                    (albums_ids.clone(), market.clone()),
+                )).unwrap_or_else(|err| {
                     error!(
-                        "Error sending {} data through track_crawl::crawl_albums_tracks_thread sender: {}",
-                        album_id,
+                        "Unexpected error in album::get_albums_loop_until_ok : {}",
                         err,
                     );
-                });
+                    ItemLookup::request_failed(albums_ids.len(), &err)
+                }).into_iter().zip(albums_csv.iter()).map(|(album_lookup, album_csv)| {
+                    progress.inc(1);
+                    telemetry.record_processed("track_crawl", 1);
 
-                progress.inc(1);
+                    album_lookup.missing_report(&album_csv.id, market).map(|missing_item| {
+                        missing_sender.send(missing_item).unwrap_or_else(|err| {
+                            error!(
+                                "Error sending missing album {} through track_crawl::crawl_albums_tracks_thread sender: {}",
+                                album_csv.id,
+                                err,
+                            );
+                        });
+                    });
+
+                    let album_full = match album_lookup {
+                        ItemLookup::Found(album_full) => album_full,
+                        ItemLookup::NotFound => {
+                            warn!("Album {} not found, skipping its tracks", album_csv.id);
+                            return;
+                        },
+                        ItemLookup::Malformed(err) => {
+                            warn!("Skipping tracks of malformed album {}: {}", album_csv.id, err);
+                            return;
+                        },
+                        ItemLookup::RequestFailed(_) => return,
+                    };
+                    let album_id = album_full.id.clone();
+                    let release_date = album_full.release_date.clone();
+                    let mut album_genres = album_csv.origin_artist_genres.clone();
+                    if !album_full.genres.is_empty() {
+                        album_genres = album_full.genres.join(", ");
+                    }
+
+                    album_full.tracks.next.map(|next_url| {
+                        next_pagings.push(NextPaging {
+                            origin_album: album_id.clone(),
+                            origin_album_genres: album_genres.clone(),
+                            release_date: release_date.clone(),
+                            market: market.clone(),
+                            url: next_url.clone(),
+                        });
+                    });
+                    album_full.tracks.items.into_iter().map(|track_simple| {
+                        sender.send(TrackCsv::extract_from(
+                            track_simple,
+                            album_id.clone(),
+                            album_genres.clone(),
+                            release_date.clone(),
+                            market.clone(),
+                        )).map_err(|err| SimpleError {
+                            message: err.to_string(),
+                            kind: ErrorKind::Permanent,
+                        }.into())
+                    }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
+                        error!(
+                            "Error sending {} data through track_crawl::crawl_albums_tracks_thread sender: {}",
+                            album_id,
+                            err,
+                        );
+                    });
+                }).last();
             }).last();
 
             while let Some(next_paging) = next_pagings.pop() {
@@ -131,6 +181,8 @@ fn crawl_albums_tracks_thread(
                         next_pagings.push(NextPaging{
                             origin_album: origin_album.clone(),
                             origin_album_genres: next_paging.origin_album_genres.clone(),
+                            release_date: next_paging.release_date.clone(),
+                            market: next_paging.market.clone(),
                             url: next_url,
                         });
                     });
@@ -139,8 +191,11 @@ fn crawl_albums_tracks_thread(
                             track_simple,
                             origin_album.clone(),
                             next_paging.origin_album_genres.clone(),
+                            next_paging.release_date.clone(),
+                            next_paging.market.clone(),
                         )).map_err(|err| SimpleError {
                             message: err.to_string(),
+                            kind: ErrorKind::Permanent,
                         }.into())
                     }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
                         error!(
@@ -163,28 +218,34 @@ fn crawl_albums_tracks_thread(
 
 pub fn track_crawl(
     albums_crawled: Receiver<Vec<AlbumCsv>>,
+    markets: Vec<Option<Market>>,
     client_ring: Arc<RwLock<ClientRing>>,
     sender: Sender<TrackCsv>,
+    missing_sender: Sender<MissingItemCsv>,
 ) -> thread::Result<()> {
     let progress = Arc::new(ProgressBar::new(
         (lines_from_file("albums_crawled.csv")
          .expect("Error in reading artists crawled")
-         .len() - 1) as u64
+         .len() - 1) as u64 * markets.len() as u64
     ));
     progress.set_style(
         ProgressStyle::default_bar()
             .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
     );
 
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let num_threads = num_cpus::get();
     info!("Using {} threads", num_threads);
 
     let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
         crawl_albums_tracks_thread(
             albums_crawled.clone(),
+            markets.clone(),
             client_ring.clone(),
             sender.clone(),
+            missing_sender.clone(),
             progress.clone(),
+            telemetry.clone(),
         )
     }).collect();
 
@@ -193,20 +254,33 @@ pub fn track_crawl(
     }).collect()
 }
 
+// Crawls every album once per market, or once without a market if none are
+// given. Albums missing in a market go to albums_missing.csv.
 #[allow(dead_code)]
 pub fn track_crawl_main(
+    markets: Vec<Market>,
     client_ring: Arc<RwLock<ClientRing>>,
 ) {
+    let markets: Vec<Option<Market>> = if markets.is_empty() {
+        vec![None]
+    } else {
+        markets.into_iter().map(Some).collect()
+    };
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let (album_sender, album_receiver) = channel::unbounded();
     let (track_sender, track_receiver) = channel::unbounded();
-    
+    let (missing_sender, missing_receiver) = channel::unbounded();
+
+    telemetry.register_queue("track_crawl_input", album_receiver.clone());
+    telemetry.register_queue("track_crawl_output", track_receiver.clone());
+
     let reader_thread = thread::spawn(move || {
         read_csv_chunks_into_sender(20, album_sender, "albums_crawled.csv")
             .expect("Error in reading albums crawled")
     });
 
     let crawler_thread = thread::spawn(move || {
-        track_crawl(album_receiver, client_ring, track_sender)
+        track_crawl(album_receiver, markets, client_ring, track_sender, missing_sender)
             .expect("Error in crawling tracks");
     });
 
@@ -215,6 +289,11 @@ pub fn track_crawl_main(
             .expect("Error in writing tracks");
     });
 
+    let missing_writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(missing_receiver, "albums_missing.csv")
+            .expect("Error in writing missing albums");
+    });
+
     reader_thread.join().unwrap_or_else(|err| {
         error!("Error in track reader thread: {:?}", err);
     });
@@ -226,4 +305,13 @@ pub fn track_crawl_main(
     writer_thread.join().unwrap_or_else(|err| {
         error!("Error in track writer thread: {:?}", err);
     });
+
+    missing_writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in missing albums writer thread: {:?}", err);
+    });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("track_crawl").unwrap_or_else(|err| {
+        error!("Error in writing track_crawl client stats: {}", err);
+    });
 }
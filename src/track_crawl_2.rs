@@ -42,60 +42,88 @@ use crate::{
         read_csv_into_sender,
         write_csv_through_receiver,
     },
+    market::{
+        Market,
+    },
+    telemetry::{
+        Telemetry,
+    },
     track_types::{
+        TopTrackCsv,
         TrackCsv2,
+        TrackFull,
     },
     utils::{
+        ErrorKind,
         loop_until_ok,
         SimpleError,
     },
 };
 
-fn crawl_artists_tracks_thread(
+// Makes an output row from a top track of an artist in a market, ranked from
+// 1.
+type ExtractTopTrack<S> = fn(TrackFull, &ArtistCsv, &Market, usize) -> S;
+
+fn crawl_artists_top_tracks_thread<S: 'static + Send>(
     artists_crawled: Receiver<ArtistCsv>,
+    markets: Vec<Market>,
     client_ring: Arc<RwLock<ClientRing>>,
-    sender: Sender<TrackCsv2>,
+    sender: Sender<S>,
+    extract: ExtractTopTrack<S>,
+    stage: &'static str,
     progress: Arc<ProgressBar>,
+    telemetry: Arc<Telemetry>,
 ) -> thread::JoinHandle<()> {
     thread::spawn(move || {
         let mut rt = Runtime::new().expect("No tokio runtime");
 
         while let Some(artist_csv) = artists_crawled.recv().ok() {
-            rt.block_on(loop_until_ok(
-                &get_artist_top_tracks,
-                client_ring.clone(),
-                artist_csv.id.clone(),
-            )).unwrap_or_else(|err| {
-                error!(
-                    "Unexpected error in artist::get_artist_top_tracks for {}: {}",
-                    artist_csv.id,
-                    err,
-                );
-                vec![]
-            }).into_iter().map(|track_full| {
-                sender.send(TrackCsv2::extract_from(
-                    track_full,
-                    &artist_csv,
-                )).map_err(|err| SimpleError {
-                    message: err.to_string(),
-                }.into())
-            }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
-                error!(
-                    "Error sending {} data through track_crawl_2::crawl_artists_tracks_thread sender: {}",
-                    artist_csv.id,
-                    err,
-                );
-            });
+            markets.iter().map(|market| {
+                rt.block_on(loop_until_ok(
+                    &get_artist_top_tracks,
+                    client_ring.clone(),
+                    (artist_csv.id.clone(), market.clone()),
+                )).unwrap_or_else(|err| {
+                    error!(
+                        "Unexpected error in artist::get_artist_top_tracks for {} in {}: {}",
+                        artist_csv.id,
+                        market,
+                        err,
+                    );
+                    vec![]
+                }).into_iter().enumerate().map(|(index, track_full)| {
+                    sender.send(extract(
+                        track_full,
+                        &artist_csv,
+                        market,
+                        index + 1,
+                    )).map_err(|err| SimpleError {
+                        message: err.to_string(),
+                        kind: ErrorKind::Permanent,
+                    }.into())
+                }).collect::<Result<(), Box<dyn Error>>>().unwrap_or_else(|err| {
+                    error!(
+                        "Error sending {} data through {} sender: {}",
+                        artist_csv.id,
+                        stage,
+                        err,
+                    );
+                });
+            }).last();
 
             progress.inc(1);
+            telemetry.record_processed(stage, 1);
         }
     })
 }
 
-pub fn track_crawl(
+fn crawl_artists_top_tracks<S: 'static + Send>(
     artists_crawled: Receiver<ArtistCsv>,
+    markets: Vec<Market>,
     client_ring: Arc<RwLock<ClientRing>>,
-    sender: Sender<TrackCsv2>,
+    sender: Sender<S>,
+    extract: ExtractTopTrack<S>,
+    stage: &'static str,
 ) -> thread::Result<()> {
     let progress = Arc::new(ProgressBar::new(
         (lines_from_file("artists_crawled.csv")
@@ -107,40 +135,73 @@ pub fn track_crawl(
             .template("[{elapsed_precise}] [{wide_bar}] {pos}/{len} ({percent}%)")
     );
 
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let num_threads = num_cpus::get();
-    info!("Using {} threads", num_threads);
-    
+    info!("Using {} threads for {} markets", num_threads, markets.len());
+
     let threads: Vec<thread::JoinHandle<()>> = (0..num_threads).map(|_| {
-        crawl_artists_tracks_thread(
+        crawl_artists_top_tracks_thread(
             artists_crawled.clone(),
+            markets.clone(),
             client_ring.clone(),
             sender.clone(),
+            extract,
+            stage,
             progress.clone(),
+            telemetry.clone(),
         )
     }).collect();
 
     threads.into_iter().map(|join_handle| {
         join_handle.join()
     }).collect::<thread::Result<()>>().and_then(|res| {
-        progress.finish_with_message("Done crawling tracks");
+        progress.finish_with_message(&format!("Done crawling {}", stage)[..]);
         Ok(res)
     })
 }
 
+pub fn track_crawl(
+    artists_crawled: Receiver<ArtistCsv>,
+    markets: Vec<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    sender: Sender<TrackCsv2>,
+) -> thread::Result<()> {
+    crawl_artists_top_tracks(
+        artists_crawled,
+        markets,
+        client_ring,
+        sender,
+        |track_full, artist_csv, market, _| TrackCsv2::extract_from(track_full, artist_csv, market.clone()),
+        "track_crawl_2",
+    )
+}
+
+// Top tracks are per market, so every artist is crawled once per market.
 #[allow(dead_code)]
 pub fn track_crawl_main(
+    markets: Vec<Market>,
     client_ring: Arc<RwLock<ClientRing>>,
 ) {
+    let markets = if markets.is_empty() {
+        warn!("No markets given for top tracks, using US");
+        vec![Market::Country("US".to_string())]
+    } else {
+        markets
+    };
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
     let (artist_sender, artist_receiver) = channel::unbounded();
     let (track_sender, track_receiver) = channel::unbounded();
 
+    telemetry.register_queue("track_crawl_2_input", artist_receiver.clone());
+    telemetry.register_queue("track_crawl_2_output", track_receiver.clone());
+
     let reader_thread = thread::spawn(move || {
         read_csv_into_sender(artist_sender, "artists_crawled.csv")
             .expect("Error in reading artists crawled")
     });
 
     let crawler_thread = thread::spawn(move || {
-        track_crawl(artist_receiver, client_ring, track_sender)
+        track_crawl(artist_receiver, markets, client_ring, track_sender)
             .expect("Error in crawling tracks");
     });
 
@@ -160,4 +221,74 @@ pub fn track_crawl_main(
     writer_thread.join().unwrap_or_else(|err| {
         error!("Error in track writer thread: {:?}", err);
     });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("track_crawl_2").unwrap_or_else(|err| {
+        error!("Error in writing track_crawl_2 client stats: {}", err);
+    });
+}
+
+pub fn top_tracks_by_market(
+    artists_crawled: Receiver<ArtistCsv>,
+    markets: Vec<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+    sender: Sender<TopTrackCsv>,
+) -> thread::Result<()> {
+    crawl_artists_top_tracks(
+        artists_crawled,
+        markets,
+        client_ring,
+        sender,
+        |track_full, artist_csv, market, rank| {
+            TopTrackCsv::extract_from(track_full, artist_csv.id.clone(), market.clone(), rank)
+        },
+        "top_tracks_by_market",
+    )
+}
+
+// Long-format top tracks (artist, market, rank, track) for comparing what
+// artists are known for across regions.
+#[allow(dead_code)]
+pub fn top_tracks_by_market_main(
+    markets: Vec<Market>,
+    client_ring: Arc<RwLock<ClientRing>>,
+) {
+    let telemetry = client_ring.read().expect("client ring RwLock poisoned").telemetry();
+    let (artist_sender, artist_receiver) = channel::unbounded();
+    let (top_track_sender, top_track_receiver) = channel::unbounded();
+
+    telemetry.register_queue("top_tracks_by_market_input", artist_receiver.clone());
+    telemetry.register_queue("top_tracks_by_market_output", top_track_receiver.clone());
+
+    let reader_thread = thread::spawn(move || {
+        read_csv_into_sender(artist_sender, "artists_crawled.csv")
+            .expect("Error in reading artists crawled")
+    });
+
+    let crawler_thread = thread::spawn(move || {
+        top_tracks_by_market(artist_receiver, markets, client_ring, top_track_sender)
+            .expect("Error in crawling top tracks by market");
+    });
+
+    let writer_thread = thread::spawn(move || {
+        write_csv_through_receiver(top_track_receiver, "top_tracks_by_market.csv")
+            .expect("Error in writing top tracks by market");
+    });
+
+    reader_thread.join().unwrap_or_else(|err| {
+        error!("Error in top tracks reader thread: {:?}", err);
+    });
+
+    crawler_thread.join().unwrap_or_else(|err| {
+        error!("Error in top tracks crawler thread: {:?}", err);
+    });
+
+    writer_thread.join().unwrap_or_else(|err| {
+        error!("Error in top tracks writer thread: {:?}", err);
+    });
+
+    telemetry.log_summary();
+    telemetry.dump_stage("top_tracks_by_market").unwrap_or_else(|err| {
+        error!("Error in writing top_tracks_by_market client stats: {}", err);
+    });
 }
@@ -6,6 +6,7 @@ use std::{
         PartialOrd,
         Ordering,
     },
+    fmt,
     ops::{
         Div,
         Mul,
@@ -23,6 +24,14 @@ use num::{
     },
 };
 use serde::{
+    de::{
+        DeserializeSeed,
+        Deserializer,
+        IgnoredAny,
+        MapAccess,
+        SeqAccess,
+        Visitor,
+    },
     Deserialize,
     Serialize,
 };
@@ -35,10 +44,26 @@ use crate::{
     album_types::{
         AlbumSimple,
     },
+    api_enums::{
+        Key,
+        Mode,
+        ObjectType,
+    },
     artist_types::{
         ArtistCsv,
         ArtistSimple,
     },
+    ids::{
+        AlbumId,
+        ArtistId,
+        TrackId,
+    },
+    market::{
+        Market,
+    },
+    release_date::{
+        ReleaseDate,
+    },
 };
 
 #[derive(Debug, Deserialize, Serialize)]
@@ -86,6 +111,266 @@ pub struct AudioAnalysis {
     pub tatums: Vec<TimeInterval>,
 }
 
+// Which per-segment aggregates an analysis summary computes. Counts are
+// always kept.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AnalysisAggregates {
+    pub pitches: bool,
+    pub timbre: bool,
+    // rotate pitches to the key of the section each segment mostly falls in
+    pub adjust_pitches: bool,
+}
+
+// Mean and standard deviation in one pass (Welford).
+#[derive(Clone, Debug, Default)]
+pub struct RunningStats {
+    count: usize,
+    mean: f64,
+    m2: f64,
+}
+
+impl RunningStats {
+    pub fn push(
+        &mut self,
+        value: f32,
+    ) {
+        let value = value as f64;
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    pub fn mean(
+        &self,
+    ) -> f32 {
+        self.mean as f32
+    }
+
+    pub fn stdev(
+        &self,
+    ) -> f32 {
+        if self.count == 0 {
+            return 0.0;
+        }
+        (self.m2 / self.count as f64).sqrt() as f32
+    }
+}
+
+// An audio analysis reduced while it is decoded, so segments are folded one
+// at a time instead of being materialized. Sections are kept, being few, and
+// precede segments in API responses; segments seen before any section are not
+// key adjusted.
+#[derive(Debug, Default)]
+pub struct AnalysisSummary {
+    pub num_bars: usize,
+    pub num_beats: usize,
+    pub num_sections: usize,
+    pub num_segments: usize,
+    pub num_tatums: usize,
+    pub pitches: Option<Vec<RunningStats>>,
+    pub timbre: Option<Vec<RunningStats>>,
+}
+
+impl AnalysisSummary {
+    fn fold_segment(
+        &mut self,
+        mut segment: Segment,
+        sections: &Vec<Section>,
+        aggregates: &AnalysisAggregates,
+    ) {
+        self.num_segments += 1;
+
+        if let Some(ref mut pitches) = self.pitches {
+            if aggregates.adjust_pitches && !sections.is_empty() {
+                // -1 when no key was detected
+                let key = segment.get_key(sections);
+                if key >= 0 {
+                    adjust_pitches(&mut segment.pitches, key);
+                }
+            }
+            pitches.iter_mut().zip(segment.pitches.iter()).map(|(stats, pitch)| {
+                stats.push(*pitch);
+            }).last();
+        }
+
+        if let Some(ref mut timbre) = self.timbre {
+            timbre.iter_mut().zip(segment.timbre.iter()).map(|(stats, value)| {
+                stats.push(*value);
+            }).last();
+        }
+    }
+}
+
+pub struct AnalysisSummarySeed(pub AnalysisAggregates);
+
+impl<'de> DeserializeSeed<'de> for AnalysisSummarySeed {
+    type Value = AnalysisSummary;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_map(self)
+    }
+}
+
+impl<'de> Visitor<'de> for AnalysisSummarySeed {
+    type Value = AnalysisSummary;
+
+    fn expecting(
+        &self,
+        formatter: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        formatter.write_str("an audio analysis object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(
+        self,
+        mut map: A,
+    ) -> Result<Self::Value, A::Error> {
+        let aggregates = self.0;
+        let mut summary = AnalysisSummary {
+            pitches: if aggregates.pitches { Some(vec![RunningStats::default(); 12]) } else { None },
+            timbre: if aggregates.timbre { Some(vec![RunningStats::default(); 12]) } else { None },
+            ..Default::default()
+        };
+        let mut sections = Vec::new();
+
+        while let Some(key) = map.next_key::<String>()? {
+            match &key[..] {
+                "bars" => summary.num_bars = map.next_value_seed(CountSeq)?,
+                "beats" => summary.num_beats = map.next_value_seed(CountSeq)?,
+                "tatums" => summary.num_tatums = map.next_value_seed(CountSeq)?,
+                "sections" => {
+                    sections = map.next_value::<Vec<Section>>()?;
+                    summary.num_sections = sections.len();
+                },
+                "segments" => map.next_value_seed(SegmentFold {
+                    summary: &mut summary,
+                    sections: &sections,
+                    aggregates: &aggregates,
+                })?,
+                _ => {
+                    map.next_value::<IgnoredAny>()?;
+                },
+            }
+        }
+
+        Ok(summary)
+    }
+}
+
+// Counts the elements of an array without keeping them.
+struct CountSeq;
+
+impl<'de> DeserializeSeed<'de> for CountSeq {
+    type Value = usize;
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de> Visitor<'de> for CountSeq {
+    type Value = usize;
+
+    fn expecting(
+        &self,
+        formatter: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        formatter.write_str("an array")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<Self::Value, A::Error> {
+        let mut count = 0;
+        while let Some(IgnoredAny) = seq.next_element()? {
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+struct SegmentFold<'a> {
+    summary: &'a mut AnalysisSummary,
+    sections: &'a Vec<Section>,
+    aggregates: &'a AnalysisAggregates,
+}
+
+impl<'de, 'a> DeserializeSeed<'de> for SegmentFold<'a> {
+    type Value = ();
+
+    fn deserialize<D: Deserializer<'de>>(
+        self,
+        deserializer: D,
+    ) -> Result<Self::Value, D::Error> {
+        deserializer.deserialize_seq(self)
+    }
+}
+
+impl<'de, 'a> Visitor<'de> for SegmentFold<'a> {
+    type Value = ();
+
+    fn expecting(
+        &self,
+        formatter: &mut fmt::Formatter,
+    ) -> fmt::Result {
+        formatter.write_str("an array of segments")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(
+        self,
+        mut seq: A,
+    ) -> Result<Self::Value, A::Error> {
+        while let Some(segment) = seq.next_element::<Segment>()? {
+            self.summary.fold_segment(segment, self.sections, self.aggregates);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct AnalysisCsv {
+    pub track_id: TrackId,
+    pub num_segments: usize,
+    // 12 comma-separated values each, empty when not aggregated
+    pub pitch_means: String,
+    pub pitch_stdevs: String,
+    pub timbre_means: String,
+    pub timbre_stdevs: String,
+}
+
+fn join_stats<F: Fn(&RunningStats) -> f32>(
+    stats: &Option<Vec<RunningStats>>,
+    value: F,
+) -> String {
+    stats.as_ref().map(|stats| {
+        stats.iter().map(|stats| value(stats).to_string()).collect::<Vec<String>>().join(", ")
+    }).unwrap_or_default()
+}
+
+impl AnalysisCsv {
+    pub fn extract_from(
+        summary: &AnalysisSummary,
+        track_id: TrackId,
+    ) -> Self {
+        Self {
+            track_id,
+            num_segments: summary.num_segments,
+            pitch_means: join_stats(&summary.pitches, RunningStats::mean),
+            pitch_stdevs: join_stats(&summary.pitches, RunningStats::stdev),
+            timbre_means: join_stats(&summary.timbre, RunningStats::mean),
+            timbre_stdevs: join_stats(&summary.timbre, RunningStats::stdev),
+        }
+    }
+}
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct AudioFeatures {
     pub acousticness: f32,
@@ -93,12 +378,12 @@ pub struct AudioFeatures {
     pub danceability: f32,
     pub duration_ms: i32,
     pub energy: f32,
-    pub id: String,
+    pub id: TrackId,
     pub instrumentalness: f32,
-    pub key: i32,
+    pub key: Key,
     pub liveness: f32,
     pub loudness: f32,
-    pub mode: i32,
+    pub mode: Mode,
     pub speechiness: f32,
     pub tempo: f32,
     pub time_signature: i32,
@@ -106,17 +391,17 @@ pub struct AudioFeatures {
     pub uri: String,
     pub valence: f32,
     #[serde(rename = "type")] 
-    pub object_type: String,
+    pub object_type: ObjectType,
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TrackLink {
-    external_urls: Map<String, Value>,
-    href: String,
-    id: String,
-    uri: String,
+    pub external_urls: Map<String, Value>,
+    pub href: String,
+    pub id: TrackId,
+    pub uri: String,
     #[serde(rename = "type")]
-    pub object_type: String,
+    pub object_type: ObjectType,
 }
 
 macro_rules! with_track_core_fields {
@@ -130,7 +415,7 @@ macro_rules! with_track_core_fields {
             pub explicit: bool,
             pub external_urls: Map<String, Value>,
             pub href: String,
-            pub id: String,
+            pub id: TrackId,
             pub is_playable: Option<bool>,
             pub linked_from: Option<TrackLink>,
             pub name: String,
@@ -138,7 +423,7 @@ macro_rules! with_track_core_fields {
             pub track_number: i32,
             pub uri: String,
             #[serde(rename = "type")]
-            pub object_type: String,
+            pub object_type: ObjectType,
             $( pub $field: $ty ),*
         }
     };
@@ -155,46 +440,82 @@ with_track_core_fields!(pub struct TrackFull {
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TrackCsv {
-    pub origin_album: String,
+    pub origin_album: AlbumId,
     pub origin_album_or_origin_artist_genres: String,
-    pub id: String,
+    pub id: TrackId,
     pub name: String,
     pub track_number: i32,
+    // of the album, as sent
+    pub release_date: String,
+    #[serde(skip_deserializing)]
+    pub release_year: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub release_month: Option<u32>,
+    #[serde(skip_deserializing)]
+    pub release_decade: Option<i32>,
+    pub market: Option<Market>,
+    pub is_playable: Option<bool>,
+    // ID originally requested when the track was relinked in the market
+    pub linked_from: Option<TrackId>,
 }
 
 impl TrackCsv {
     pub fn extract_from(
         track_simple: TrackSimple,
-        origin_album: String,
+        origin_album: AlbumId,
         origin_album_or_origin_artist_genres: String,
+        release_date: String,
+        market: Option<Market>,
     ) -> Self {
+        let parsed_release_date = ReleaseDate::parse(&release_date[..]).ok();
         Self {
             origin_album: origin_album,
             origin_album_or_origin_artist_genres: origin_album_or_origin_artist_genres,
             id: track_simple.id,
             name: track_simple.name,
             track_number: track_simple.track_number,
+            release_year: parsed_release_date.map(|release_date| release_date.year()),
+            release_month: parsed_release_date.and_then(|release_date| release_date.month()),
+            release_decade: parsed_release_date.map(|release_date| release_date.decade()),
+            release_date: release_date,
+            market: market,
+            is_playable: track_simple.is_playable,
+            linked_from: track_simple.linked_from.map(|track_link| track_link.id),
         }
     }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct TrackCsv2 {
-    pub track_id: String,
-    pub origin_album: String,
+    pub track_id: TrackId,
+    pub origin_album: AlbumId,
     pub origin_album_name: String,
-    pub origin_artist: String,
+    pub origin_artist: ArtistId,
     pub origin_artist_name: String,
     pub origin_artist_genres: String,
     pub track_name: String,
     pub track_popularity: i32,
+    // of the album, as sent
+    pub release_date: String,
+    #[serde(skip_deserializing)]
+    pub release_year: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub release_month: Option<u32>,
+    #[serde(skip_deserializing)]
+    pub release_decade: Option<i32>,
+    pub market: Market,
+    pub is_playable: Option<bool>,
+    pub linked_from: Option<TrackId>,
 }
 
 impl TrackCsv2 {
     pub fn extract_from(
         track_full: TrackFull,
         origin_artist: &ArtistCsv,
+        market: Market,
     ) -> Self {
+        let parsed_release_date = track_full.album.parsed_release_date();
+        let release_date = track_full.album.release_date;
         Self {
             track_id: track_full.id,
             origin_album: track_full.album.id,
@@ -204,17 +525,67 @@ impl TrackCsv2 {
             origin_artist_genres: origin_artist.genres.clone(),
             track_name: track_full.name,
             track_popularity: track_full.popularity,
+            release_year: parsed_release_date.map(|release_date| release_date.year()),
+            release_month: parsed_release_date.and_then(|release_date| release_date.month()),
+            release_decade: parsed_release_date.map(|release_date| release_date.decade()),
+            release_date: release_date,
+            market: market,
+            is_playable: track_full.is_playable,
+            linked_from: track_full.linked_from.map(|track_link| track_link.id),
+        }
+    }
+}
+
+// One row per artist, market and top track position.
+#[derive(Debug, Deserialize, Serialize)]
+pub struct TopTrackCsv {
+    pub artist_id: ArtistId,
+    pub market: Market,
+    pub rank: usize,
+    pub track_id: TrackId,
+    pub track_name: String,
+    pub popularity: i32,
+    // of the album, as sent
+    pub release_date: String,
+    #[serde(skip_deserializing)]
+    pub release_year: Option<i32>,
+    #[serde(skip_deserializing)]
+    pub release_month: Option<u32>,
+    #[serde(skip_deserializing)]
+    pub release_decade: Option<i32>,
+}
+
+impl TopTrackCsv {
+    pub fn extract_from(
+        track_full: TrackFull,
+        artist_id: ArtistId,
+        market: Market,
+        rank: usize,
+    ) -> Self {
+        let parsed_release_date = track_full.album.parsed_release_date();
+        let release_date = track_full.album.release_date;
+        Self {
+            artist_id: artist_id,
+            market: market,
+            rank: rank,
+            track_id: track_full.id,
+            track_name: track_full.name,
+            popularity: track_full.popularity,
+            release_year: parsed_release_date.map(|release_date| release_date.year()),
+            release_month: parsed_release_date.and_then(|release_date| release_date.month()),
+            release_decade: parsed_release_date.map(|release_date| release_date.decade()),
+            release_date: release_date,
         }
     }
 }
 
 #[derive(Debug, Deserialize, Serialize)]
 pub struct FeaturesCsv {
-    pub track_id: String,
+    pub track_id: TrackId,
     // pub name: String,
     pub duration_ms: i32,
-    pub key: i32,
-    pub mode: i32,
+    pub key: Key,
+    pub mode: Mode,
     pub time_signature: i32,
     pub acousticness: f32,
     pub danceability: f32,
@@ -454,10 +825,9 @@ fn adjust_pitches(
 
 impl FeaturesCsv {
     pub fn extract_from(
-        mut analysis: AudioAnalysis,
+        summary: &AnalysisSummary,
         features: AudioFeatures,
     ) -> Self {
-        let num_segments = analysis.segments.len();
 
         // let sections = &analysis.sections;
         // let adjusted_pitches = DozenCollector::from(
@@ -492,8 +862,8 @@ impl FeaturesCsv {
             speechiness: features.speechiness,
             valence: features.valence,
             tempo: features.tempo,
-            num_sections: analysis.sections.len(),
-            num_segments: num_segments,
+            num_sections: summary.num_sections,
+            num_segments: summary.num_segments,
 
             // median_adj_pitch_0: median_sorted_vec(&adjusted_pitches.values0),
             // median_adj_pitch_1: median_sorted_vec(&adjusted_pitches.values1),
@@ -0,0 +1,126 @@
+use std::{
+    sync::{
+        Arc,
+        RwLock,
+    },
+};
+
+use futures::{
+    Future,
+};
+use reqwest::{
+    Url,
+};
+
+use crate::{
+    artist_types::{
+        ArtistFull,
+    },
+    client::{
+        ClientRing,
+    },
+    common_types::{
+        ArtistsEnvelope,
+        CursorPaging,
+        Paging,
+    },
+    market::{
+        Market,
+        with_market,
+    },
+    playlist_types::{
+        PlaylistSimple,
+    },
+    user_types::{
+        PlayHistory,
+        PublicUser,
+        SavedTrack,
+    },
+    utils::{
+        get_field_with_retry,
+        get_with_retry,
+        SimpleError,
+    },
+};
+
+type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
+
+// User IDs are free-form, so the ID is percent-encoded as its own segment.
+pub fn user_url(
+    user_id: &str,
+    path: &[&str],
+) -> Url {
+    let mut url = Url::parse("https://api.spotify.com/v1/users/").expect("Error in user::user_url format");
+    url.path_segments_mut().expect("Error in user::user_url base")
+        .pop_if_empty()
+        .push(user_id)
+        .extend(path)
+        .push("");
+    url
+}
+
+pub fn get_user(
+    client_ring: Arc<RwLock<ClientRing>>,
+    user_id: String,
+) -> CustomFuture<PublicUser> {
+    Box::new(
+        get_with_retry(
+            user_url(&user_id[..], &[]).into_string(),
+            client_ring,
+        )
+    )
+}
+
+// The endpoints below need a client ring built with ClientRing::init_user
+// holding the matching scope (user-library-read, user-follow-read,
+// user-read-recently-played, playlist-read-private).
+
+pub fn get_saved_tracks(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (offset, market): (u32, Option<Market>),
+) -> CustomFuture<Paging<SavedTrack>> {
+    Box::new(
+        get_with_retry(
+            with_market(format!("https://api.spotify.com/v1/me/tracks/?limit=50&offset={}", offset), &market),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_followed_artists(
+    client_ring: Arc<RwLock<ClientRing>>,
+    after: Option<String>,
+) -> CustomFuture<CursorPaging<ArtistFull>> {
+    let after_param = after.map(|after| format!("&after={}", after)).unwrap_or("".to_string());
+
+    get_field_with_retry::<ArtistsEnvelope<_>>(
+        format!("https://api.spotify.com/v1/me/following/?type=artist&limit=50{}", after_param),
+        client_ring,
+    )
+}
+
+pub fn get_recently_played(
+    client_ring: Arc<RwLock<ClientRing>>,
+    before: Option<String>,
+) -> CustomFuture<CursorPaging<PlayHistory>> {
+    let before_param = before.map(|before| format!("&before={}", before)).unwrap_or("".to_string());
+
+    Box::new(
+        get_with_retry(
+            format!("https://api.spotify.com/v1/me/player/recently-played/?limit=50{}", before_param),
+            client_ring,
+        )
+    )
+}
+
+pub fn get_current_user_playlists(
+    client_ring: Arc<RwLock<ClientRing>>,
+    offset: u32,
+) -> CustomFuture<Paging<PlaylistSimple>> {
+    Box::new(
+        get_with_retry(
+            format!("https://api.spotify.com/v1/me/playlists/?limit=50&offset={}", offset),
+            client_ring,
+        )
+    )
+}
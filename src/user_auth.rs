@@ -0,0 +1,330 @@
+use std::{
+    collections::{
+        HashMap,
+    },
+    error::{
+        Error,
+    },
+    io::{
+        BufRead,
+        BufReader,
+        Write,
+    },
+    net::{
+        TcpListener,
+    },
+    path::{
+        Path,
+    },
+};
+
+use futures::{
+    Future,
+};
+use rand::{
+    distributions::{
+        Alphanumeric,
+    },
+    Rng,
+    thread_rng,
+};
+use reqwest::{
+    r#async::{
+        Client,
+    },
+    Url,
+};
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use sha2::{
+    Digest,
+    Sha256,
+};
+use tokio::{
+    runtime::{
+        current_thread::{
+            Runtime,
+        },
+    },
+};
+
+use crate::{
+    credentials::{
+        Secret,
+    },
+    io::{
+        structs_from_file,
+        write_private_csv,
+    },
+    token_cache::{
+        now_secs,
+    },
+    utils::{
+        ErrorKind,
+        SimpleError,
+    },
+};
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    pub access_token: Secret,
+    pub token_type: String,
+    pub expires_in: i32,
+    pub refresh_token: Option<Secret>,
+    pub scope: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct UserToken {
+    pub client_id: String,
+    pub access_token: Secret,
+    pub refresh_token: Secret,
+    pub expires_at: u64,
+    pub scope: String,
+}
+
+impl UserToken {
+    fn from_response(
+        client_id: &str,
+        response: TokenResponse,
+        previous_refresh_token: Option<Secret>,
+        requested_scope: &str,
+    ) -> Result<Self, Box<dyn Error>> {
+        let refresh_token = response.refresh_token.or(previous_refresh_token).ok_or(SimpleError {
+            message: "No refresh token in authorization response".to_string(),
+            kind: ErrorKind::Permanent,
+        })?;
+
+        Ok(Self {
+            client_id: client_id.to_string(),
+            access_token: response.access_token,
+            refresh_token: refresh_token,
+            expires_at: now_secs() + response.expires_in.max(0) as u64,
+            scope: response.scope.unwrap_or(requested_scope.to_string()),
+        })
+    }
+
+    pub fn is_valid(
+        &self,
+    ) -> bool {
+        self.expires_at > now_secs() + 60
+    }
+
+    pub fn covers(
+        &self,
+        scopes: &[String],
+    ) -> bool {
+        let granted: Vec<&str> = self.scope.split_whitespace().collect();
+        scopes.iter().all(|scope| granted.contains(&&scope[..]))
+    }
+}
+
+pub fn load_user_token(
+    token_file: &str,
+    client_id: &str,
+) -> Option<UserToken> {
+    if !Path::new(token_file).exists() {
+        return None;
+    }
+
+    structs_from_file::<UserToken>(token_file).map(|mut user_tokens| {
+        user_tokens.drain(..).find(|user_token| user_token.client_id == client_id)
+    }).unwrap_or_else(|err| {
+        warn!("Ignoring unreadable user token file {}: {}", token_file, err);
+        None
+    })
+}
+
+// Replaces the stored token of the same client, keeping those of the others.
+pub fn save_user_token(
+    token_file: &str,
+    user_token: &UserToken,
+) -> Result<(), Box<dyn Error>> {
+    let mut user_tokens = if Path::new(token_file).exists() {
+        structs_from_file::<UserToken>(token_file).unwrap_or_else(|err| {
+            warn!("Overwriting unreadable user token file {}: {}", token_file, err);
+            vec![]
+        })
+    } else {
+        vec![]
+    };
+    user_tokens.retain(|stored_token| stored_token.client_id != user_token.client_id);
+    user_tokens.push(user_token.clone());
+    write_private_csv(user_tokens, token_file)
+}
+
+// Returns a token holding every requested scope, refreshing or running the
+// browser flow only when the stored one cannot be used.
+pub fn user_token_for(
+    token_client: &Client,
+    client_id: &str,
+    scopes: &[String],
+    port: u16,
+    token_file: &str,
+) -> Result<UserToken, Box<dyn Error>> {
+    let user_token = match load_user_token(token_file, client_id) {
+        Some(ref user_token) if user_token.covers(scopes) && user_token.is_valid() => {
+            info!("Reusing stored user token for client {}", client_id);
+            user_token.clone()
+        },
+        Some(ref user_token) if user_token.covers(scopes) => {
+            refresh_user_token(token_client, user_token).or_else(|err| {
+                warn!("Error in refreshing stored user token, authorizing again: {}", err);
+                authorize_user(token_client, client_id, scopes, port)
+            })?
+        },
+        _ => authorize_user(token_client, client_id, scopes, port)?,
+    };
+
+    save_user_token(token_file, &user_token)?;
+    Ok(user_token)
+}
+
+pub fn authorize_user(
+    token_client: &Client,
+    client_id: &str,
+    scopes: &[String],
+    port: u16,
+) -> Result<UserToken, Box<dyn Error>> {
+    let redirect_uri = format!("http://127.0.0.1:{}/callback", port);
+    let scope = scopes.join(" ");
+    let code_verifier: String = thread_rng().sample_iter(&Alphanumeric).take(64).collect();
+    let code_challenge = base64::encode_config(
+        &Sha256::digest(code_verifier.as_bytes())[..],
+        base64::URL_SAFE_NO_PAD,
+    );
+    let state: String = thread_rng().sample_iter(&Alphanumeric).take(16).collect();
+
+    let authorize_url = Url::parse_with_params(
+        "https://accounts.spotify.com/authorize",
+        &[
+            ("client_id", client_id),
+            ("response_type", "code"),
+            ("redirect_uri", &redirect_uri[..]),
+            ("code_challenge_method", "S256"),
+            ("code_challenge", &code_challenge[..]),
+            ("state", &state[..]),
+            ("scope", &scope[..]),
+        ],
+    )?;
+
+    let listener = TcpListener::bind(("127.0.0.1", port))?;
+    println!("Open this URL to authorize client {}:\n{}", client_id, authorize_url);
+
+    let (code, returned_state) = wait_for_callback(&listener)?;
+    if returned_state != state {
+        return Err(SimpleError {
+            message: "State mismatch in authorization callback".to_string(),
+            kind: ErrorKind::Permanent,
+        }.into());
+    }
+
+    let mut form_data = HashMap::new();
+    form_data.insert("grant_type", "authorization_code".to_string());
+    form_data.insert("code", code);
+    form_data.insert("redirect_uri", redirect_uri);
+    form_data.insert("client_id", client_id.to_string());
+    form_data.insert("code_verifier", code_verifier);
+
+    UserToken::from_response(client_id, request_token(token_client, form_data)?, None, &scope[..])
+}
+
+pub fn refresh_user_token(
+    token_client: &Client,
+    user_token: &UserToken,
+) -> Result<UserToken, Box<dyn Error>> {
+    info!("Refreshing user token for client {}", user_token.client_id);
+
+    let mut form_data = HashMap::new();
+    form_data.insert("grant_type", "refresh_token".to_string());
+    form_data.insert("refresh_token", user_token.refresh_token.expose().to_string());
+    form_data.insert("client_id", user_token.client_id.clone());
+
+    UserToken::from_response(
+        &user_token.client_id[..],
+        request_token(token_client, form_data)?,
+        Some(user_token.refresh_token.clone()),
+        &user_token.scope[..],
+    )
+}
+
+pub fn refresh_stored_user_token(
+    token_client: &Client,
+    client_id: &str,
+    token_file: &str,
+) -> Result<UserToken, Box<dyn Error>> {
+    let user_token = load_user_token(token_file, client_id).ok_or(SimpleError {
+        message: format!("No stored user token for client {} in {}", client_id, token_file),
+        kind: ErrorKind::Permanent,
+    })?;
+    let user_token = refresh_user_token(token_client, &user_token)?;
+
+    save_user_token(token_file, &user_token)?;
+    Ok(user_token)
+}
+
+fn request_token(
+    token_client: &Client,
+    form_data: HashMap<&str, String>,
+) -> Result<TokenResponse, Box<dyn Error>> {
+    let mut rt = Runtime::new().expect("No tokio runtime");
+
+    Ok(rt.block_on(
+        token_client.post("https://accounts.spotify.com/api/token")
+            .form(&form_data)
+            .send().and_then(|response| {
+                response.error_for_status()
+            }).and_then(|mut response| {
+                response.json::<TokenResponse>()
+            })
+    )?)
+}
+
+fn wait_for_callback(
+    listener: &TcpListener,
+) -> Result<(String, String), Box<dyn Error>> {
+    for stream in listener.incoming() {
+        let mut stream = stream?;
+
+        let mut request_line = String::new();
+        BufReader::new(&stream).read_line(&mut request_line)?;
+        let target = request_line.split_whitespace().nth(1).unwrap_or("").to_string();
+
+        if !target.starts_with("/callback") {
+            stream.write_all(b"HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\n\r\n")?;
+            continue;
+        }
+
+        let body = "Authorization received, you can close this tab.";
+        write!(
+            stream,
+            "HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\nContent-Length: {}\r\n\r\n{}",
+            body.len(),
+            body,
+        )?;
+
+        let params: HashMap<String, String> = Url::parse(&format!("http://127.0.0.1{}", target))?
+            .query_pairs().into_owned().collect();
+        if let Some(error) = params.get("error") {
+            return Err(SimpleError {
+                message: format!("Authorization denied: {}", error),
+                kind: ErrorKind::Permanent,
+            }.into());
+        }
+
+        return match (params.get("code"), params.get("state")) {
+            (Some(code), Some(state)) => Ok((code.clone(), state.clone())),
+            _ => Err(SimpleError {
+                message: "Missing code or state in authorization callback".to_string(),
+                kind: ErrorKind::Permanent,
+            }.into()),
+        };
+    }
+
+    Err(SimpleError {
+        message: "Authorization listener closed".to_string(),
+        kind: ErrorKind::Permanent,
+    }.into())
+}
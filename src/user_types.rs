@@ -0,0 +1,78 @@
+use serde::{
+    Deserialize,
+    Serialize,
+};
+use serde_json::{
+    Map,
+    Value,
+};
+
+use crate::{
+    artist_types::{
+        Followers,
+    },
+    common_types::{
+        Image,
+    },
+    track_types::{
+        TrackFull,
+        TrackSimple,
+    },
+};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PublicUser {
+    pub display_name: Option<String>,
+    pub external_urls: Map<String, Value>,
+    pub followers: Option<Followers>,
+    pub href: String,
+    pub id: String,
+    pub images: Option<Vec<Image>>,
+    pub uri: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct UserCsv {
+    pub id: String,
+    pub display_name: String,
+    pub followers_total: Option<i32>,
+    pub num_public_playlists: usize,
+}
+
+impl UserCsv {
+    pub fn extract_from(
+        public_user: PublicUser,
+        num_public_playlists: usize,
+    ) -> Self {
+        Self {
+            id: public_user.id,
+            display_name: public_user.display_name.unwrap_or("".to_string()),
+            followers_total: public_user.followers.map(|followers| followers.total),
+            num_public_playlists: num_public_playlists,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SavedTrack {
+    pub added_at: String,
+    pub track: TrackFull,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct Context {
+    pub external_urls: Map<String, Value>,
+    pub href: Option<String>,
+    pub uri: String,
+    #[serde(rename = "type")]
+    pub object_type: String,
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct PlayHistory {
+    pub track: TrackSimple,
+    pub played_at: String,
+    pub context: Option<Context>,
+}
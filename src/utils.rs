@@ -2,6 +2,10 @@ use std::{
     clone::{
         Clone,
     },
+    cmp,
+    collections::{
+        HashMap,
+    },
     fmt::{
         Display,
         Formatter,
@@ -10,6 +14,10 @@ use std::{
     error::{
         Error,
     },
+    io::{
+        self,
+        Read,
+    },
     sync::{
         Arc,
         RwLock,
@@ -17,16 +25,26 @@ use std::{
     thread,
     time::{
         Duration,
+        Instant,
     },
 };
 
+use crossbeam_channel::{
+    self as channel,
+    Receiver,
+};
 use futures::{
     future,
+    sync::{
+        oneshot,
+    },
     Future,
+    Stream,
 };
 use reqwest::{
     StatusCode,
     r#async::{
+        Chunk,
         Response,
     },
     header::{
@@ -54,13 +72,35 @@ use crate::{
         ClientRing,
     },
     common_types::{
+        Envelope,
+        MissingItemCsv,
         Paging,
     },
+    decode::{
+        decode_at,
+        decode_body,
+        decode_field,
+    },
+    market::{
+        Market,
+    },
 };
 
+// Whether loop_until_ok should try again.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorKind {
+    // transport failures and error statuses other than 404
+    Transient,
+    // the requested object does not exist
+    NotFound,
+    // retrying would not help, e.g. a body our types cannot decode
+    Permanent,
+}
+
 #[derive(Debug, Clone)]
 pub struct SimpleError {
     pub message: String,
+    pub kind: ErrorKind,
 }
 
 impl Display for SimpleError {
@@ -81,36 +121,36 @@ impl Error for SimpleError {
 
 type CustomFuture<T> = Box<Future<Item = T, Error = Box<SimpleError>>>;
 
-pub fn search<D: 'static + DeserializeOwned>(
-    query: String,
-    type_: &str,
-    client_ring: Arc<RwLock<ClientRing>>,
-) -> CustomFuture<D> {
-    get_with_retry::<D>(
-        format!(
-            "https://api.spotify.com/v1/search/?q={}&type={}",
-            query.replace(" ", "%20"),
-            type_,
-        ),
-        client_ring,
-    )
-}
+// how far a streamed body may get ahead of its decoder, in chunks
+const STREAMED_CHUNKS_IN_FLIGHT: usize = 16;
 
-pub fn get_with_retry<D: 'static + DeserializeOwned>(
+// Rotates clients on rate limits and expired tokens until the response is
+// 200, without reading its body.
+fn get_response_with_retry(
     url: String,
     client_ring: Arc<RwLock<ClientRing>>,
-) -> CustomFuture<D> {
-    let (client, token) = client_ring.read().expect("client ring RwLock poisoned").front();
+) -> CustomFuture<Response> {
+    let (client, token, client_key, telemetry) = {
+        let client_ring_read = client_ring.read().expect("client ring RwLock poisoned");
+        let (client, token, client_key) = client_ring_read.front();
+        (client, token, client_key, client_ring_read.telemetry())
+    };
+    let telemetry_clone = telemetry.clone();
+    let client_key_clone = client_key.clone();
+    let start = Instant::now();
     Box::new(
         client.get(&url[..])
-            .header(reqwest::header::AUTHORIZATION, &*format!("Bearer {}", token))
-            .send().map_err(|err| SimpleError {
-                message: err.to_string(),
-            }.into()).and_then(|mut response| {
+            .header(reqwest::header::AUTHORIZATION, &*format!("Bearer {}", token.expose()))
+            .send().map_err(move |err| {
+                telemetry_clone.record_transport_error(&client_key_clone[..], start.elapsed());
+                SimpleError {
+                    message: err.to_string(),
+                    kind: ErrorKind::Transient,
+                }.into()
+            }).and_then(move |response| -> CustomFuture<Response> {
+                telemetry.record_response(&client_key[..], response.status(), start.elapsed());
                 match response.status() {
-                    StatusCode::OK => Box::new(response.json::<D>().map_err(|err| SimpleError {
-                        message: err.to_string(),
-                    }.into())),
+                    StatusCode::OK => Box::new(future::ok(response)),
                     StatusCode::TOO_MANY_REQUESTS => {
                         match response.headers().get(RETRY_AFTER) {
                             Some(header_value) => {
@@ -119,20 +159,28 @@ pub fn get_with_retry<D: 'static + DeserializeOwned>(
                                 (*client_ring.write().expect("client ring RwLock poisoned")).sleep_front_and_get_next(
                                     duration.parse::<u64>().expect("Unexpected format in retry-after header")
                                 );
-                                get_with_retry::<D>(url, client_ring)
+                                get_response_with_retry(url, client_ring)
                             },
                             None => Box::new(future::err(Box::new(SimpleError {
                                 message: "No retry-after header".to_string(),
+                                kind: ErrorKind::Transient,
                             }))),
                         }
                     },
                     StatusCode::UNAUTHORIZED => {
                         (*client_ring.write().expect("client ring RwLock poisoned")).refresh_front_and_get_next();
-                        get_with_retry::<D>(url, client_ring)
+                        get_response_with_retry(url, client_ring)
+                    },
+                    StatusCode::NOT_FOUND => {
+                        Box::new(future::err(Box::new(SimpleError {
+                            message: format!("Not found: {}", url),
+                            kind: ErrorKind::NotFound,
+                        })))
                     },
                     status_code => {
                         Box::new(future::err(Box::new(SimpleError {
                             message: format!("Unexpected error code: {}", status_code),
+                            kind: ErrorKind::Transient,
                         })))
                     },
                 }
@@ -140,6 +188,119 @@ pub fn get_with_retry<D: 'static + DeserializeOwned>(
     )
 }
 
+pub fn get_body_with_retry(
+    url: String,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> CustomFuture<Chunk> {
+    Box::new(
+        get_response_with_retry(
+            url,
+            client_ring,
+        ).and_then(|response| {
+            response.into_body().concat2().map_err(|err| SimpleError {
+                message: err.to_string(),
+                kind: ErrorKind::Transient,
+            }.into())
+        })
+    )
+}
+
+// A body read as its chunks arrive through a channel.
+pub struct ChunkReader {
+    chunks: Receiver<Chunk>,
+    current: Option<Chunk>,
+    position: usize,
+}
+
+impl Read for ChunkReader {
+    fn read(
+        &mut self,
+        buf: &mut [u8],
+    ) -> io::Result<usize> {
+        loop {
+            if let Some(ref chunk) = self.current {
+                if self.position < chunk.len() {
+                    let len = cmp::min(buf.len(), chunk.len() - self.position);
+                    buf[..len].copy_from_slice(&chunk[self.position..self.position + len]);
+                    self.position += len;
+                    return Ok(len);
+                }
+            }
+
+            match self.chunks.recv() {
+                Ok(chunk) => {
+                    self.current = Some(chunk);
+                    self.position = 0;
+                },
+                // the body ended, or failed and the failure is returned by
+                // get_streamed_with_retry
+                Err(_) => return Ok(0),
+            }
+        }
+    }
+}
+
+// Decodes the body on its own thread while it arrives, so at most
+// STREAMED_CHUNKS_IN_FLIGHT chunks of it are held at once.
+pub fn get_streamed_with_retry<T, F>(
+    url: String,
+    client_ring: Arc<RwLock<ClientRing>>,
+    decode: F,
+) -> CustomFuture<T>
+where
+    T: 'static + Send,
+    F: 'static + Send + FnOnce(ChunkReader) -> Result<T, Box<SimpleError>>,
+{
+    Box::new(
+        get_response_with_retry(
+            url.clone(),
+            client_ring,
+        ).and_then(move |response| {
+            let (chunk_sender, chunk_receiver) = channel::bounded(STREAMED_CHUNKS_IN_FLIGHT);
+            let (decoded_sender, decoded_receiver) = oneshot::channel();
+            thread::spawn(move || {
+                decoded_sender.send(decode(ChunkReader {
+                    chunks: chunk_receiver,
+                    current: None,
+                    position: 0,
+                })).ok();
+            });
+
+            response.into_body().map_err(|err| -> Box<SimpleError> {
+                Box::new(SimpleError {
+                    message: err.to_string(),
+                    kind: ErrorKind::Transient,
+                })
+            }).for_each(move |chunk| {
+                // fails once the decoder gave up, the rest is drained
+                chunk_sender.send(chunk).ok();
+                Ok(())
+            }).and_then(move |_| {
+                decoded_receiver.map_err(move |_| Box::new(SimpleError {
+                    message: format!("Decoder of {} panicked", url),
+                    kind: ErrorKind::Permanent,
+                }))
+            }).and_then(|decoded| decoded)
+        })
+    )
+}
+
+pub fn get_with_retry<D: 'static + DeserializeOwned>(
+    url: String,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> CustomFuture<D> {
+    let decode_context = client_ring.read().expect("client ring RwLock poisoned").decode_context();
+
+    Box::new(
+        get_body_with_retry(
+            url.clone(),
+            client_ring,
+        ).and_then(move |body| {
+            decode_body(&body[..], &url[..], &decode_context)
+        })
+    )
+}
+
 pub fn get_next_paging<D: 'static + DeserializeOwned>(
     client_ring: Arc<RwLock<ClientRing>>,
     url: String,
@@ -152,6 +313,148 @@ pub fn get_next_paging<D: 'static + DeserializeOwned>(
     )
 }
 
+pub fn get_field_with_retry<E: 'static + Envelope + DeserializeOwned>(
+    url: String,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> CustomFuture<E::Inner> {
+    Box::new(
+        get_with_retry::<E>(
+            url,
+            client_ring,
+        ).map(Envelope::into_inner)
+    )
+}
+
+// Next pages of browse and search listings come wrapped in an object keyed by
+// the listing type, e.g. {"albums": {...paging...}}, and nothing else.
+pub fn get_next_wrapped_paging<D: 'static + DeserializeOwned>(
+    client_ring: Arc<RwLock<ClientRing>>,
+    (url, key): (String, String),
+) -> CustomFuture<Paging<D>> {
+    Box::new(
+        get_with_retry::<HashMap<String, Paging<D>>>(
+            url.clone(),
+            client_ring,
+        ).and_then(move |mut wrapped| {
+            wrapped.remove(&key[..]).ok_or(Box::new(SimpleError {
+                message: format!("No {} in wrapped paging from {}", key, url),
+                kind: ErrorKind::Permanent,
+            }))
+        })
+    )
+}
+
+// Outcome for one ID of a multiple-item endpoint. Unknown IDs, and tracks
+// without audio features, come back as null. RequestFailed is every ID of a
+// response that failed as a whole.
+#[derive(Debug)]
+pub enum ItemLookup<T> {
+    Found(T),
+    NotFound,
+    Malformed(String),
+    RequestFailed(String),
+}
+
+impl<T> ItemLookup<T> {
+    pub fn request_failed(
+        num_ids: usize,
+        err: &SimpleError,
+    ) -> Vec<Self> {
+        (0..num_ids).map(|_| ItemLookup::RequestFailed(err.message.clone())).collect()
+    }
+
+    pub fn found(
+        self,
+    ) -> Option<T> {
+        match self {
+            ItemLookup::Found(item) => Some(item),
+            _ => None,
+        }
+    }
+
+    // None for found items.
+    pub fn missing_report<I: Display>(
+        &self,
+        id: &I,
+        market: &Option<Market>,
+    ) -> Option<MissingItemCsv> {
+        let (status, error) = match self {
+            ItemLookup::Found(_) => return None,
+            ItemLookup::NotFound => ("not_found", String::new()),
+            ItemLookup::Malformed(err) => ("malformed", err.clone()),
+            ItemLookup::RequestFailed(err) => ("request_failed", err.clone()),
+        };
+        Some(MissingItemCsv {
+            id: id.to_string(),
+            market: market.clone(),
+            status: status.to_string(),
+            error: error,
+        })
+    }
+}
+
+// One lookup per requested ID, in request order. Only a broken envelope, or
+// one with more or fewer items than IDs, fails the whole response, and
+// neither is retried. Bodies are decoded in one pass unless an item is
+// malformed or schema drift is tallied, which take a slower pass per item.
+pub fn get_item_lookups_with_retry<T, E>(
+    url: String,
+    num_ids: usize,
+    client_ring: Arc<RwLock<ClientRing>>,
+) -> CustomFuture<Vec<ItemLookup<T>>>
+where
+    T: 'static + DeserializeOwned,
+    E: 'static + Envelope<Inner = Vec<Option<T>>> + DeserializeOwned,
+{
+    let decode_context = client_ring.read().expect("client ring RwLock poisoned").decode_context();
+
+    Box::new(
+        get_body_with_retry(
+            url.clone(),
+            client_ring,
+        ).and_then(move |body| {
+            let items = match decode_context.schema_drift {
+                Some(_) => None,
+                None => serde_json::from_slice::<E>(&body[..]).ok().map(|envelope| {
+                    envelope.into_inner().into_iter().map(|item| {
+                        item.map(ItemLookup::Found).unwrap_or(ItemLookup::NotFound)
+                    }).collect::<Vec<ItemLookup<T>>>()
+                }),
+            };
+
+            let items = match items {
+                Some(items) => items,
+                None => decode_body::<Value>(&body[..], &url[..], &decode_context).and_then(|value| {
+                    decode_field::<Vec<Value>>(&value, E::KEY, &url[..], &decode_context)
+                })?.iter().enumerate().map(|(index, item)| {
+                    match item {
+                        Value::Null => ItemLookup::NotFound,
+                        item => decode_at(
+                            item,
+                            &format!("{}[{}]", E::KEY, index)[..],
+                            &url[..],
+                            &decode_context,
+                        ).map(ItemLookup::Found).unwrap_or_else(|err| {
+                            ItemLookup::Malformed(err.message)
+                        }),
+                    }
+                }).collect(),
+            };
+
+            if items.len() != num_ids {
+                return Err(Box::new(SimpleError {
+                    message: format!("Got {} {} for {} IDs from {}", items.len(), E::KEY, num_ids, url),
+                    kind: ErrorKind::Permanent,
+                }));
+            }
+
+            Ok(items)
+        })
+    )
+}
+
+// Retries transient errors until the endpoint succeeds. Missing objects and
+// undecodable responses are returned as they are.
 pub fn loop_until_ok<Input: Clone, OkReturn>(
     api_endpoint: &'static Fn(
         Arc<RwLock<ClientRing>>,
@@ -164,8 +467,11 @@ pub fn loop_until_ok<Input: Clone, OkReturn>(
         api_endpoint(
             client_ring.clone(),
             input.clone(),
-        ).or_else(move |_| {
-            info!("Error in utils::loop_until_ok, retrying");
+        ).or_else(move |err| -> CustomFuture<OkReturn> {
+            if err.kind != ErrorKind::Transient {
+                return Box::new(future::err(err));
+            }
+            info!("Error in utils::loop_until_ok, retrying: {}", err);
             thread::sleep(Duration::from_secs(3));
             loop_until_ok(
                 api_endpoint,